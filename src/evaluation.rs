@@ -1,14 +1,61 @@
 use crate::{
-    Variable,
+    AngleUnit, Variable,
+    lexer::Location,
     multivector::Multivector,
-    parsing::{AstExpression, AstExpressionKind, BinaryOperator, UnaryOperator},
+    parsing::{AstExpression, AstExpressionKind, BinaryOperator, TernaryOperator, UnaryOperator},
 };
+use derive_more::Display;
 use std::collections::BTreeMap;
+use thiserror::Error;
 
-pub fn evaluate_expression(
-    expression: &AstExpression,
+#[derive(Debug, Error)]
+#[error("{location}: {kind}")]
+pub struct EvaluationError<'source> {
+    pub location: Location,
+    pub kind: EvaluationErrorKind<'source>,
+}
+
+#[derive(Debug, Display)]
+pub enum EvaluationErrorKind<'source> {
+    #[display("Unknown variable '{_0}'")]
+    UnknownVariable(&'source str),
+    #[display("Cannot invert a multivector that is null or not a versor")]
+    NotInvertible,
+    #[display("Cannot rotate around an ideal point")]
+    IdealRotationCenter,
+    #[display("Cannot construct a parallel line through an ideal point")]
+    IdealParallelPoint,
+    #[display("Cannot find the area of a triangle with an ideal point")]
+    IdealAreaPoint,
+    #[display("Cannot find the distance between a point and a line if either is ideal")]
+    IdealDistanceArgument,
+    #[display("Evaluation exceeded budget")]
+    BudgetExceeded,
+    #[display("Expected a scalar argument but got '{_0}'")]
+    ExpectedScalar(Multivector),
+    #[display("'{_0}' is not a versor so it does not act as a transform")]
+    NotAVersor(Multivector),
+}
+
+/// The tolerance used when checking if a value is a versor
+pub const VERSOR_EPSILON: f32 = 1e-4;
+
+pub fn evaluate_expression<'source>(
+    expression: &AstExpression<'source>,
     variables: &BTreeMap<String, Variable>,
-) -> Result<Multivector, String> {
+    angle_unit: AngleUnit,
+    strict_scalars: bool,
+    warnings: &mut Vec<EvaluationError<'source>>,
+    budget: &mut usize,
+) -> Result<Multivector, EvaluationError<'source>> {
+    // every expression evaluated costs one step, so a huge script stops instead of freezing the ui.
+    // Scripts have no loops or user functions, so each expression runs once and the steps used
+    // are the size of the code, see `AstExpression::node_count`
+    *budget = budget.checked_sub(1).ok_or(EvaluationError {
+        location: expression.location,
+        kind: EvaluationErrorKind::BudgetExceeded,
+    })?;
+
     Ok(match expression.kind {
         AstExpressionKind::Name {
             name,
@@ -16,10 +63,10 @@ pub fn evaluate_expression(
         } => match variables.get(name) {
             Some(variable) => variable.value,
             None => {
-                return Err(format!(
-                    "{}: Unknown variable '{name}'",
-                    name_token.location
-                ));
+                return Err(EvaluationError {
+                    location: name_token.location,
+                    kind: EvaluationErrorKind::UnknownVariable(name),
+                });
             }
         },
         AstExpressionKind::Number {
@@ -31,13 +78,21 @@ pub fn evaluate_expression(
         },
         AstExpressionKind::Unary {
             ref operator,
-            operator_token: _,
+            ref operator_token,
             ref operand,
         } => {
-            let operand = evaluate_expression(operand, variables)?;
+            let operand = evaluate_expression(
+                operand,
+                variables,
+                angle_unit,
+                strict_scalars,
+                warnings,
+                budget,
+            )?;
             match operator {
                 UnaryOperator::Negate => -operand,
                 UnaryOperator::Dual => operand.dual(),
+                UnaryOperator::AntiDual => operand.antidual(),
                 UnaryOperator::Reverse => operand.reverse(),
                 UnaryOperator::Normalise => operand.normalized(),
                 UnaryOperator::Magnitude => Multivector {
@@ -45,22 +100,65 @@ pub fn evaluate_expression(
                     ..Multivector::ZERO
                 },
                 UnaryOperator::Sin => Multivector {
-                    s: f32::sin(operand.s),
+                    s: f32::sin(angle_unit.to_radians(scalar_argument(
+                        operand,
+                        operator_token.location,
+                        strict_scalars,
+                        warnings,
+                    )?)),
                     ..Multivector::ZERO
                 },
                 UnaryOperator::Cos => Multivector {
-                    s: f32::cos(operand.s),
+                    s: f32::cos(angle_unit.to_radians(scalar_argument(
+                        operand,
+                        operator_token.location,
+                        strict_scalars,
+                        warnings,
+                    )?)),
                     ..Multivector::ZERO
                 },
                 UnaryOperator::ASin => Multivector {
-                    s: f32::asin(operand.s),
+                    s: angle_unit.from_radians(f32::asin(scalar_argument(
+                        operand,
+                        operator_token.location,
+                        strict_scalars,
+                        warnings,
+                    )?)),
                     ..Multivector::ZERO
                 },
                 UnaryOperator::ACos => Multivector {
-                    s: f32::acos(operand.s),
+                    s: angle_unit.from_radians(f32::acos(scalar_argument(
+                        operand,
+                        operator_token.location,
+                        strict_scalars,
+                        warnings,
+                    )?)),
                     ..Multivector::ZERO
                 },
                 UnaryOperator::Exp => operand.exp(),
+                UnaryOperator::IsVersor => Multivector {
+                    s: if operand.is_versor(VERSOR_EPSILON) {
+                        1.0
+                    } else {
+                        0.0
+                    },
+                    ..Multivector::ZERO
+                },
+                UnaryOperator::Inverse => match operand.inverse() {
+                    Some(inverse) => inverse,
+                    None => {
+                        return Err(EvaluationError {
+                            location: operator_token.location,
+                            kind: EvaluationErrorKind::NotInvertible,
+                        });
+                    }
+                },
+                UnaryOperator::Rotor => Multivector::rotor(angle_unit.to_radians(scalar_argument(
+                    operand,
+                    operator_token.location,
+                    strict_scalars,
+                    warnings,
+                )?)),
             }
         }
         AstExpressionKind::Binary {
@@ -69,19 +167,335 @@ pub fn evaluate_expression(
             ref operator_token,
             ref right,
         } => {
-            let left = evaluate_expression(left, variables)?;
-            let right = evaluate_expression(right, variables)?;
+            let left = evaluate_expression(
+                left,
+                variables,
+                angle_unit,
+                strict_scalars,
+                warnings,
+                budget,
+            )?;
+            let right = evaluate_expression(
+                right,
+                variables,
+                angle_unit,
+                strict_scalars,
+                warnings,
+                budget,
+            )?;
             match operator {
                 BinaryOperator::Add => left + right,
                 BinaryOperator::Subtract => left - right,
                 BinaryOperator::Multiply => left * right,
-                BinaryOperator::Divide => {
-                    return Err(format!("{}: Divide unimplemented", operator_token.location));
-                }
+                BinaryOperator::Divide => match right.inverse() {
+                    Some(inverse) => left * inverse,
+                    None => {
+                        return Err(EvaluationError {
+                            location: operator_token.location,
+                            kind: EvaluationErrorKind::NotInvertible,
+                        });
+                    }
+                },
                 BinaryOperator::Wedge => left.wedge(right),
                 BinaryOperator::Inner => left.inner(right),
+                BinaryOperator::HestenesInner => left.hestenes_inner(right),
                 BinaryOperator::Regressive => left.regressive(right),
+                BinaryOperator::Dot => Multivector {
+                    s: left.dot(right),
+                    ..Multivector::ZERO
+                },
+                BinaryOperator::Translator => Multivector::translator(
+                    scalar_argument(left, operator_token.location, strict_scalars, warnings)?,
+                    scalar_argument(right, operator_token.location, strict_scalars, warnings)?,
+                ),
+                BinaryOperator::Reflect => {
+                    if !right.is_versor(VERSOR_EPSILON) {
+                        warnings.push(EvaluationError {
+                            location: operator_token.location,
+                            kind: EvaluationErrorKind::NotAVersor(right),
+                        });
+                    }
+                    left.reflect(right)
+                }
+                BinaryOperator::LieBracket => left.lie_bracket(right),
+                BinaryOperator::RotorBetween => Multivector::rotor_between(left, right),
+                BinaryOperator::Perpendicular => left.perpendicular(right),
+                BinaryOperator::Parallel => match left.parallel(right) {
+                    Some(line) => line,
+                    None => {
+                        return Err(EvaluationError {
+                            location: operator_token.location,
+                            kind: EvaluationErrorKind::IdealParallelPoint,
+                        });
+                    }
+                },
+                BinaryOperator::DistPointLine => {
+                    match Multivector::point_line_distance(left, right) {
+                        Some(distance) => Multivector {
+                            s: distance,
+                            ..Multivector::ZERO
+                        },
+                        None => {
+                            return Err(EvaluationError {
+                                location: operator_token.location,
+                                kind: EvaluationErrorKind::IdealDistanceArgument,
+                            });
+                        }
+                    }
+                }
+                BinaryOperator::MotorAlong => {
+                    let amount =
+                        scalar_argument(right, operator_token.location, strict_scalars, warnings)?;
+                    // finite points rotate by an angle like `rotor_about`, ideal points translate by a distance
+                    let amount = if left.grade2().magnitude() > 0.0 {
+                        angle_unit.to_radians(amount)
+                    } else {
+                        amount
+                    };
+                    Multivector::motor_along(left, amount)
+                }
+                BinaryOperator::RotorAbout => {
+                    match Multivector::rotor_about(
+                        left,
+                        angle_unit.to_radians(scalar_argument(
+                            right,
+                            operator_token.location,
+                            strict_scalars,
+                            warnings,
+                        )?),
+                    ) {
+                        Some(motor) => motor,
+                        None => {
+                            return Err(EvaluationError {
+                                location: operator_token.location,
+                                kind: EvaluationErrorKind::IdealRotationCenter,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        AstExpressionKind::Ternary {
+            ref operator,
+            ref operator_token,
+            ref first,
+            ref second,
+            ref third,
+        } => {
+            let first = evaluate_expression(
+                first,
+                variables,
+                angle_unit,
+                strict_scalars,
+                warnings,
+                budget,
+            )?;
+            let second = evaluate_expression(
+                second,
+                variables,
+                angle_unit,
+                strict_scalars,
+                warnings,
+                budget,
+            )?;
+            let third = evaluate_expression(
+                third,
+                variables,
+                angle_unit,
+                strict_scalars,
+                warnings,
+                budget,
+            )?;
+            match operator {
+                TernaryOperator::TriangleArea => {
+                    match Multivector::triangle_area(first, second, third) {
+                        Some(area) => Multivector {
+                            s: area,
+                            ..Multivector::ZERO
+                        },
+                        None => {
+                            return Err(EvaluationError {
+                                location: operator_token.location,
+                                kind: EvaluationErrorKind::IdealAreaPoint,
+                            });
+                        }
+                    }
+                }
+                // the weight for each grade is the length of the third argument's components of that grade,
+                // so `blend_grades(a, b, 1 + e12)` takes the scalar and bivector parts from `b`, and the
+                // weights can't be negative since `e1 - e2` is a grade 1 weight of `√2` rather than `0`
+                TernaryOperator::BlendGrades => first.blend_grades(
+                    second,
+                    [0, 1, 2, 3].map(|grade| {
+                        third
+                            .grade(grade)
+                            .components()
+                            .into_iter()
+                            .map(|(_, value)| value * value)
+                            .sum::<f32>()
+                            .sqrt()
+                    }),
+                ),
+                // these follow the glsl functions and only look at the scalar parts
+                TernaryOperator::Clamp | TernaryOperator::SmoothStep => {
+                    let mut scalars = [0.0; 3];
+                    for (scalar, value) in scalars.iter_mut().zip([first, second, third]) {
+                        *scalar = scalar_argument(
+                            value,
+                            operator_token.location,
+                            strict_scalars,
+                            warnings,
+                        )?;
+                    }
+                    let [first, second, third] = scalars;
+                    let s = if let TernaryOperator::Clamp = operator {
+                        first.max(second).min(third)
+                    } else {
+                        let t = ((third - first) / (second - first)).clamp(0.0, 1.0);
+                        t * t * (3.0 - 2.0 * t)
+                    };
+                    Multivector {
+                        s,
+                        ..Multivector::ZERO
+                    }
+                }
             }
         }
     })
 }
+
+/// Builtins that only use the scalar part of an argument check that it has no other parts,
+/// which is an error when `strict_scalars` is set and otherwise a warning
+fn scalar_argument<'source>(
+    value: Multivector,
+    location: Location,
+    strict_scalars: bool,
+    warnings: &mut Vec<EvaluationError<'source>>,
+) -> Result<f32, EvaluationError<'source>> {
+    if !value.is_scalar() {
+        let error = EvaluationError {
+            location,
+            kind: EvaluationErrorKind::ExpectedScalar(value),
+        };
+        if strict_scalars {
+            return Err(error);
+        }
+        warnings.push(error);
+    }
+    Ok(value.scalar())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::parse_expression;
+
+    fn evaluate(source: &str) -> Multivector {
+        let variables = ["e0", "e1", "e2", "e01", "e02", "e12", "e012"]
+            .into_iter()
+            .map(|name| {
+                (
+                    name.to_string(),
+                    Variable {
+                        value: Multivector::basis_blade(name).unwrap(),
+                        frozen: None,
+                        display: None,
+                        group: None,
+                    },
+                )
+            })
+            .collect();
+        evaluate_expression(
+            &parse_expression(source).unwrap(),
+            &variables,
+            AngleUnit::Radians,
+            false,
+            &mut vec![],
+            &mut 1000,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn blend_grades_weights_each_grade() {
+        let a = "(1 + e1 + e12 + e012)";
+        let b = "(2 + 2 * e2 + 2 * e01 + 2 * e012)";
+        assert_eq!(
+            evaluate(&format!("blend_grades({a}, {b}, 1 + e12)")),
+            Multivector {
+                s: 2.0,
+                e1: 1.0,
+                e01: 2.0,
+                e012: 1.0,
+                ..Multivector::ZERO
+            }
+        );
+        // opposite components don't cancel out
+        assert_eq!(
+            evaluate(&format!("blend_grades({a}, {b}, 0.6 * e1 - 0.8 * e2)")),
+            evaluate(&format!("blend_grades({a}, {b}, e1)")),
+        );
+    }
+
+    #[test]
+    fn displayed_values_parse_back() {
+        for value in [
+            Multivector::ZERO,
+            Multivector {
+                s: -1.5,
+                e0: 0.1,
+                e1: -1.0,
+                e2: 1.0,
+                e01: 3.25,
+                e02: -0.001,
+                e12: 1e-3,
+                e012: 2.0,
+            },
+            Multivector::rotor(1.0),
+        ] {
+            assert_eq!(evaluate(&value.to_string()), value, "{value}");
+        }
+    }
+
+    #[test]
+    fn clamp_and_smoothstep_saturate() {
+        let scalar = |s| Multivector {
+            s,
+            ..Multivector::ZERO
+        };
+        assert_eq!(evaluate("clamp(5, 0, 1)"), scalar(1.0));
+        assert_eq!(evaluate("clamp(-5, 0, 1)"), scalar(0.0));
+        assert_eq!(evaluate("clamp(0.25, 0, 1)"), scalar(0.25));
+        assert_eq!(evaluate("smoothstep(0, 2, -1)"), scalar(0.0));
+        assert_eq!(evaluate("smoothstep(0, 2, 3)"), scalar(1.0));
+        assert_eq!(evaluate("smoothstep(0, 2, 1)"), scalar(0.5));
+    }
+
+    #[test]
+    fn budget_is_one_step_per_expression() {
+        let expression = parse_expression("1 + 2 * 3").unwrap();
+        let evaluate_with_budget = |mut budget| {
+            let result = evaluate_expression(
+                &expression,
+                &BTreeMap::new(),
+                AngleUnit::Radians,
+                false,
+                &mut vec![],
+                &mut budget,
+            );
+            (result, budget)
+        };
+
+        let (result, budget) = evaluate_with_budget(expression.node_count());
+        assert_eq!(result.unwrap().s, 7.0);
+        assert_eq!(budget, 0);
+        let (result, _) = evaluate_with_budget(expression.node_count() - 1);
+        assert!(matches!(
+            result,
+            Err(EvaluationError {
+                kind: EvaluationErrorKind::BudgetExceeded,
+                ..
+            })
+        ));
+    }
+}
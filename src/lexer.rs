@@ -2,7 +2,7 @@ use derive_more::{Debug, Display};
 use std::{iter::Peekable, str::CharIndices};
 use thiserror::Error;
 
-#[derive(Debug, Display, Clone, Copy)]
+#[derive(Debug, Display, Clone, Copy, PartialEq)]
 #[display("{line}:{column}")]
 pub struct Location {
     pub position: usize,
@@ -29,6 +29,8 @@ pub enum LexerErrorKind {
 #[display("{kind}")]
 pub struct Token<'source> {
     pub location: Location,
+    /// The location just after the last character of the token
+    pub end: Location,
     pub kind: TokenKind<'source>,
 }
 
@@ -50,6 +52,44 @@ pub enum TokenKind<'source> {
     ACosKeyword,
     #[display("exp")]
     ExpKeyword,
+    #[display("inverse")]
+    InverseKeyword,
+    #[display("dot")]
+    DotKeyword,
+    #[display("rotor")]
+    RotorKeyword,
+    #[display("translator")]
+    TranslatorKeyword,
+    #[display("rotor_about")]
+    RotorAboutKeyword,
+    #[display("reflect")]
+    ReflectKeyword,
+    #[display("bracket")]
+    BracketKeyword,
+    #[display("rotor_between")]
+    RotorBetweenKeyword,
+    #[display("clamp")]
+    ClampKeyword,
+    #[display("smoothstep")]
+    SmoothStepKeyword,
+    #[display("perpendicular")]
+    PerpendicularKeyword,
+    #[display("parallel")]
+    ParallelKeyword,
+    #[display("hestenes")]
+    HestenesKeyword,
+    #[display("area")]
+    AreaKeyword,
+    #[display("is_versor")]
+    IsVersorKeyword,
+    #[display("motor_along")]
+    MotorAlongKeyword,
+    #[display("antidual")]
+    AntiDualKeyword,
+    #[display("blend_grades")]
+    BlendGradesKeyword,
+    #[display("dist_point_line")]
+    DistPointLineKeyword,
     #[display("{_0}")]
     Number(f32),
     #[display("(")]
@@ -58,6 +98,8 @@ pub enum TokenKind<'source> {
     CloseParenthesis,
     #[display(";")]
     Semicolon,
+    #[display(",")]
+    Comma,
     #[display("+")]
     Plus,
     #[display("-")]
@@ -125,70 +167,81 @@ impl<'source> Lexer<'source> {
     pub fn next_token(&mut self) -> Result<Option<Token<'source>>, LexerError> {
         loop {
             let start_location = self.location;
-            break Ok(Some(Token {
-                location: start_location,
-                kind: match self.next_char() {
-                    None => return Ok(None),
-
-                    Some('(') => TokenKind::OpenParenthesis,
-                    Some(')') => TokenKind::CloseParenthesis,
-                    Some(';') => TokenKind::Semicolon,
-                    Some('+') => TokenKind::Plus,
-                    Some('-') => TokenKind::Minus,
-                    Some('*') => TokenKind::Asterisk,
-                    Some('/') => TokenKind::Slash,
-                    Some('^') => TokenKind::Caret,
-                    Some('|') => TokenKind::Pipe,
-                    Some('&') => TokenKind::Ampersand,
-                    Some('!') => TokenKind::ExclamationMark,
-                    Some('~') => TokenKind::Tilde,
-                    Some('=') => TokenKind::Equal,
-
-                    Some(c) if c.is_alphabetic() || c == '_' => {
-                        while let Some(c) = self.peek_char()
-                            && (c.is_alphanumeric() || c == '_')
-                        {
-                            self.next_char();
-                        }
-
-                        let end_location = self.location;
-                        match &self.source[start_location.position..end_location.position] {
-                            "normalize" => TokenKind::NormalizeKeyword,
-                            "magnitude" => TokenKind::MagnitudeKeyword,
-                            "sin" => TokenKind::SinKeyword,
-                            "cos" => TokenKind::CosKeyword,
-                            "asin" => TokenKind::ASinKeyword,
-                            "acos" => TokenKind::ACosKeyword,
-                            "exp" => TokenKind::ExpKeyword,
-                            name => TokenKind::Name(name),
-                        }
+            let kind = match self.next_char() {
+                None => return Ok(None),
+
+                Some('(') => TokenKind::OpenParenthesis,
+                Some(')') => TokenKind::CloseParenthesis,
+                Some(';') => TokenKind::Semicolon,
+                Some(',') => TokenKind::Comma,
+                Some('+') => TokenKind::Plus,
+                Some('-') => TokenKind::Minus,
+                Some('*') => TokenKind::Asterisk,
+                Some('/') if self.peek_char() == Some('/') => {
+                    while let Some(c) = self.peek_char()
+                        && c != '\n'
+                    {
+                        self.next_char();
                     }
+                    continue;
+                }
+                Some('/') => TokenKind::Slash,
+                Some('^') => TokenKind::Caret,
+                Some('|') => TokenKind::Pipe,
+                Some('&') => TokenKind::Ampersand,
+                Some('!') => TokenKind::ExclamationMark,
+                Some('~') => TokenKind::Tilde,
+                Some('=') => TokenKind::Equal,
 
-                    Some(c) if c.is_numeric() => {
-                        while let Some(c) = self.peek_char()
-                            && (c.is_numeric() || c == '.')
-                        {
-                            self.next_char();
-                        }
-
-                        let end_location = self.location;
-                        let value = self.source[start_location.position..end_location.position]
-                            .parse()
-                            .map_err(|_| LexerError {
-                                location: start_location,
-                                kind: LexerErrorKind::InvalidNumber,
-                            })?;
-                        TokenKind::Number(value)
+                Some(c) if c.is_alphabetic() || c == '_' => {
+                    while let Some(c) = self.peek_char()
+                        && (c.is_alphanumeric() || c == '_')
+                    {
+                        self.next_char();
                     }
 
-                    Some(c) if c.is_whitespace() => continue,
-                    Some(c) => {
-                        return Err(LexerError {
-                            location: start_location,
-                            kind: LexerErrorKind::UnexpectedChar(c),
-                        });
+                    let end_location = self.location;
+                    let name = &self.source[start_location.position..end_location.position];
+                    // builtins are only recognised right before their arguments,
+                    // so scripts can still use their names for variables
+                    let called = self.source[end_location.position..]
+                        .trim_start()
+                        .starts_with('(');
+                    match builtin_keyword(name) {
+                        Some(keyword) if called => keyword,
+                        _ => TokenKind::Name(name),
                     }
-                },
+                }
+
+                Some(c) if c.is_numeric() => {
+                    while let Some(c) = self.peek_char()
+                        && (c.is_numeric() || c == '.')
+                    {
+                        self.next_char();
+                    }
+
+                    let end_location = self.location;
+                    let value = self.source[start_location.position..end_location.position]
+                        .parse()
+                        .map_err(|_| LexerError {
+                            location: start_location,
+                            kind: LexerErrorKind::InvalidNumber,
+                        })?;
+                    TokenKind::Number(value)
+                }
+
+                Some(c) if c.is_whitespace() => continue,
+                Some(c) => {
+                    return Err(LexerError {
+                        location: start_location,
+                        kind: LexerErrorKind::UnexpectedChar(c),
+                    });
+                }
+            };
+            break Ok(Some(Token {
+                location: start_location,
+                end: self.location,
+                kind,
             }));
         }
     }
@@ -197,3 +250,163 @@ impl<'source> Lexer<'source> {
         self.clone().next_token()
     }
 }
+
+/// The keyword for the builtin function called `name`, if there is one
+fn builtin_keyword<'source>(name: &str) -> Option<TokenKind<'source>> {
+    Some(match name {
+        "normalize" => TokenKind::NormalizeKeyword,
+        "magnitude" => TokenKind::MagnitudeKeyword,
+        "sin" => TokenKind::SinKeyword,
+        "cos" => TokenKind::CosKeyword,
+        "asin" => TokenKind::ASinKeyword,
+        "acos" => TokenKind::ACosKeyword,
+        "exp" => TokenKind::ExpKeyword,
+        "inverse" => TokenKind::InverseKeyword,
+        "dot" => TokenKind::DotKeyword,
+        "rotor" => TokenKind::RotorKeyword,
+        "translator" => TokenKind::TranslatorKeyword,
+        "rotor_about" => TokenKind::RotorAboutKeyword,
+        "reflect" => TokenKind::ReflectKeyword,
+        "bracket" => TokenKind::BracketKeyword,
+        "rotor_between" => TokenKind::RotorBetweenKeyword,
+        "clamp" => TokenKind::ClampKeyword,
+        "smoothstep" => TokenKind::SmoothStepKeyword,
+        "perpendicular" => TokenKind::PerpendicularKeyword,
+        "parallel" => TokenKind::ParallelKeyword,
+        "hestenes" => TokenKind::HestenesKeyword,
+        "area" => TokenKind::AreaKeyword,
+        "is_versor" => TokenKind::IsVersorKeyword,
+        "motor_along" => TokenKind::MotorAlongKeyword,
+        "antidual" => TokenKind::AntiDualKeyword,
+        "blend_grades" => TokenKind::BlendGradesKeyword,
+        "dist_point_line" => TokenKind::DistPointLineKeyword,
+        _ => return None,
+    })
+}
+
+/// Whether `name` lexes as a single name, so a script can refer to it. Builtin names like `sin` are
+/// names unless they are called, so they can be used for variables
+pub fn is_identifier(name: &str) -> bool {
+    matches!(
+        Lexer::new(name).next_token(),
+        Ok(Some(Token {
+            location,
+            end,
+            kind: TokenKind::Name(_),
+        })) if location.position == 0 && end.position == name.len()
+    )
+}
+
+/// The closest identifier to `name`, replacing anything that cannot be in a name with `_`
+pub fn sanitize_identifier(name: &str) -> String {
+    let mut sanitized = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if !sanitized.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUILTIN_NAMES: [&str; 26] = [
+        "normalize",
+        "magnitude",
+        "sin",
+        "cos",
+        "asin",
+        "acos",
+        "exp",
+        "inverse",
+        "dot",
+        "rotor",
+        "translator",
+        "rotor_about",
+        "reflect",
+        "bracket",
+        "rotor_between",
+        "clamp",
+        "smoothstep",
+        "perpendicular",
+        "parallel",
+        "hestenes",
+        "area",
+        "is_versor",
+        "motor_along",
+        "antidual",
+        "blend_grades",
+        "dist_point_line",
+    ];
+
+    #[test]
+    fn builtin_names_are_identifiers() {
+        for name in BUILTIN_NAMES {
+            assert!(builtin_keyword(name).is_some(), "{name} is not a builtin");
+            assert!(is_identifier(name), "{name} is not an identifier");
+        }
+    }
+
+    #[test]
+    fn builtin_names_are_keywords_when_called() {
+        for name in BUILTIN_NAMES {
+            for source in [format!("{name}(x)"), format!("{name} (x)")] {
+                let token = Lexer::new(&source).next_token().unwrap().unwrap();
+                assert!(
+                    !matches!(token.kind, TokenKind::Name(_)),
+                    "{source} is not a call"
+                );
+            }
+            let source = format!("{name} + 1");
+            let token = Lexer::new(&source).next_token().unwrap().unwrap();
+            assert!(matches!(token.kind, TokenKind::Name(lexed) if lexed == name));
+        }
+    }
+
+    #[test]
+    fn sanitized_names_are_identifiers() {
+        for name in [
+            "", "  ", "my point", "2d", "a-b", "π/2", "x'", "sin", "ok_name",
+        ] {
+            let sanitized = sanitize_identifier(name);
+            assert!(is_identifier(&sanitized), "{name:?} became {sanitized:?}");
+        }
+        assert_eq!(sanitize_identifier("ok_name"), "ok_name");
+        assert_eq!(sanitize_identifier("2d"), "_2d");
+    }
+
+    #[test]
+    fn tokens_end_after_their_last_character() {
+        let source = "ab + 12.5\n  sin(c)";
+        let mut lexer = Lexer::new(source);
+        let mut spans = vec![];
+        while let Some(token) = lexer.next_token().unwrap() {
+            spans.push((
+                &source[token.location.position..token.end.position],
+                (token.end.line, token.end.column),
+            ));
+        }
+        assert_eq!(
+            spans,
+            [
+                ("ab", (1, 3)),
+                ("+", (1, 5)),
+                ("12.5", (1, 10)),
+                ("sin", (2, 6)),
+                ("(", (2, 7)),
+                ("c", (2, 8)),
+                (")", (2, 9)),
+            ]
+        );
+    }
+}
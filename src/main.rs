@@ -1,14 +1,20 @@
 use crate::{
-    evaluation::evaluate_expression,
+    evaluation::{EvaluationError, EvaluationErrorKind, VERSOR_EPSILON, evaluate_expression},
+    lexer::{Location, is_identifier, sanitize_identifier},
     multivector::Multivector,
-    parsing::{AstStatementKind, parse},
-    rendering::{GpuCamera, GpuObject, RenderData, RenderState},
+    parsing::{
+        AstExpression, AstStatement, AstStatementKind, ParseError, ParseErrorKind, parse,
+        parse_expression, parse_lenient,
+    },
+    rendering::{GpuCamera, GpuObject, GpuPolygonVertex, RenderData, RenderState},
 };
+use cgmath::InnerSpace;
+use derive_more::Display;
 use eframe::{egui, wgpu};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BTreeMap, HashSet},
-    sync::atomic::{AtomicU8, Ordering},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
 };
 
 pub mod evaluation;
@@ -18,41 +24,135 @@ pub mod parsing;
 pub mod rendering;
 
 static GA_FLAVOUR: AtomicU8 = AtomicU8::new(0);
+/// Negates the dual, for following texts that use the opposite orientation of the pseudoscalar
+static FLIPPED_ORIENTATION: AtomicBool = AtomicBool::new(false);
 
 #[derive(Serialize, Deserialize)]
 #[serde(default)]
 struct App {
     #[serde(skip)]
     last_time: Option<std::time::Instant>,
+    #[serde(skip)]
+    gpu_error: Option<String>,
+    #[serde(skip)]
+    last_save: Option<std::time::Instant>,
+    #[serde(skip)]
+    last_save_duration: Option<std::time::Duration>,
+    auto_save_interval: f32,
+    angle_unit: AngleUnit,
+    script_angle_unit: bool,
+    scientific_notation: bool,
+    #[serde(skip)]
+    scene_text: String,
+    #[serde(skip)]
+    scene_error: Option<String>,
+    #[serde(skip)]
+    saved_snapshot: Option<SavedSnapshot>,
+    changes_window_open: bool,
+    theme: Theme,
+    product_table_window_open: bool,
+    product_table: TableProduct,
     info_window_open: bool,
     camera_window_open: bool,
     camera: Camera,
+    #[serde(skip)]
+    recenter_point: Option<String>,
     parameters_window_open: bool,
     parameters: Vec<Parameter>,
+    #[serde(skip)]
+    import_text: String,
+    #[serde(skip)]
+    import_errors: Vec<String>,
+    drivers_window_open: bool,
+    drivers: Vec<Driver>,
+    statistics_driver: Option<String>,
+    statistics_variable: Option<String>,
+    statistics_samples: usize,
+    #[serde(skip)]
+    statistics: Option<SweepStatistics>,
+    animation_window_open: bool,
+    animation: Animation,
+    /// Waiting for the user to confirm before it is done
+    #[serde(skip)]
+    pending_clear: Option<ClearAction>,
+    #[serde(skip)]
+    undo_stack: Vec<UndoEntry>,
+    bake: Bake,
+    #[serde(skip)]
+    bake_status: Option<Result<String, String>>,
     code_window_open: bool,
-    errors: Vec<String>,
+    #[serde(skip)]
+    errors: Vec<Diagnostic>,
+    #[serde(skip)]
+    selected_error: Option<usize>,
+    #[serde(skip)]
+    warnings: Vec<Diagnostic>,
+    warn_unused_variables: bool,
+    collapse_long_errors: bool,
+    strict_scalar_arguments: bool,
+    parallel_evaluation: bool,
+    evaluation_budget: usize,
+    #[serde(skip)]
+    selection_output: Vec<String>,
     code: String,
     variables_window_open: bool,
     variables: BTreeMap<String, Variable>,
+    clean_values: bool,
+    display_defaults: DisplayDefaults,
+    pinned_variables: Vec<String>,
+    hidden_groups: BTreeSet<String>,
+    /// The parameters whose headers are expanded, so they stay expanded after a restart
+    expanded_parameters: BTreeSet<String>,
+    /// The variables whose headers are expanded, so they stay expanded after a restart
+    expanded_variables: BTreeSet<String>,
+    #[serde(skip)]
+    intersection_lines: (Option<String>, Option<String>),
+    #[serde(skip)]
+    join_points: (Option<String>, Option<String>),
+    joins: Vec<Join>,
+    #[serde(skip)]
+    orbit_selection: (Option<String>, Option<String>),
+    orbits: Vec<Orbit>,
+    #[serde(skip)]
+    transform_all_motor: Option<String>,
+    #[serde(skip)]
+    polygon_points: String,
+    polygons: Vec<Polygon>,
+    #[serde(skip)]
+    measure_selection: (Option<String>, Option<String>),
+    measurement: Option<(String, String)>,
+    #[serde(skip)]
+    product_selection: (Option<String>, Option<String>),
+    product_decomposition: Option<(String, String)>,
+    #[serde(skip)]
+    dragged_parameter: Option<usize>,
+    /// The camera transform when the current gizmo drag started
+    #[serde(skip)]
+    gizmo_drag_start: Option<Multivector>,
 }
 
 impl Default for App {
     fn default() -> Self {
         Self {
             last_time: None,
+            gpu_error: None,
+            last_save: None,
+            last_save_duration: None,
+            auto_save_interval: 30.0,
+            angle_unit: AngleUnit::Radians,
+            script_angle_unit: false,
+            scientific_notation: false,
+            scene_text: String::new(),
+            scene_error: None,
+            saved_snapshot: None,
+            changes_window_open: false,
+            theme: Theme::Dark,
+            product_table_window_open: false,
+            product_table: TableProduct::Geometric,
             info_window_open: true,
             camera_window_open: true,
-            camera: Camera {
-                transform: Multivector {
-                    s: 1.0,
-                    ..Multivector::ZERO
-                },
-                view_height: 10.0,
-                move_speed: 1.0,
-                zoom_speed: 2.0,
-                line_thickness: 0.1,
-                point_radius: 0.1,
-            },
+            camera: Camera::default(),
+            recenter_point: None,
             parameters_window_open: true,
             parameters: vec![
                 Parameter {
@@ -62,6 +162,8 @@ impl Default for App {
                         e0: 1.0,
                         ..Multivector::ZERO
                     },
+                    keep_normalized: false,
+                    polar: false,
                 },
                 Parameter {
                     name: "e1".into(),
@@ -70,6 +172,8 @@ impl Default for App {
                         e1: 1.0,
                         ..Multivector::ZERO
                     },
+                    keep_normalized: false,
+                    polar: false,
                 },
                 Parameter {
                     name: "e2".into(),
@@ -78,6 +182,8 @@ impl Default for App {
                         e2: 1.0,
                         ..Multivector::ZERO
                     },
+                    keep_normalized: false,
+                    polar: false,
                 },
                 Parameter {
                     name: "e01".into(),
@@ -86,6 +192,8 @@ impl Default for App {
                         e01: 1.0,
                         ..Multivector::ZERO
                     },
+                    keep_normalized: false,
+                    polar: false,
                 },
                 Parameter {
                     name: "e02".into(),
@@ -94,6 +202,8 @@ impl Default for App {
                         e02: 1.0,
                         ..Multivector::ZERO
                     },
+                    keep_normalized: false,
+                    polar: false,
                 },
                 Parameter {
                     name: "e12".into(),
@@ -102,6 +212,8 @@ impl Default for App {
                         e12: 1.0,
                         ..Multivector::ZERO
                     },
+                    keep_normalized: false,
+                    polar: false,
                 },
                 Parameter {
                     name: "e012".into(),
@@ -110,10 +222,34 @@ impl Default for App {
                         e012: 1.0,
                         ..Multivector::ZERO
                     },
+                    keep_normalized: false,
+                    polar: false,
                 },
             ],
+            import_text: String::new(),
+            import_errors: vec![],
+            drivers_window_open: false,
+            drivers: vec![],
+            statistics_driver: None,
+            statistics_variable: None,
+            statistics_samples: 100,
+            statistics: None,
+            animation_window_open: false,
+            animation: Animation::default(),
+            pending_clear: None,
+            undo_stack: vec![],
+            bake: Bake::default(),
+            bake_status: None,
             code_window_open: true,
             errors: vec![],
+            selected_error: None,
+            warnings: vec![],
+            warn_unused_variables: false,
+            collapse_long_errors: true,
+            strict_scalar_arguments: false,
+            parallel_evaluation: false,
+            evaluation_budget: DEFAULT_EVALUATION_BUDGET,
+            selection_output: vec![],
             code: String::new(),
             variables_window_open: true,
             variables: BTreeMap::from([
@@ -121,6 +257,8 @@ impl Default for App {
                     "e1".into(),
                     Variable {
                         value: Multivector::ZERO,
+                        frozen: None,
+                        group: None,
                         display: Some(VariableDisplay {
                             color: cgmath::Vector3 {
                                 x: 1.0,
@@ -128,6 +266,9 @@ impl Default for App {
                                 z: 0.0,
                             },
                             layer: 0.0,
+                            label: None,
+                            point_style: PointStyle::Disc,
+                            z_order: 0,
                         }),
                     },
                 ),
@@ -135,6 +276,8 @@ impl Default for App {
                     "e2".into(),
                     Variable {
                         value: Multivector::ZERO,
+                        frozen: None,
+                        group: None,
                         display: Some(VariableDisplay {
                             color: cgmath::Vector3 {
                                 x: 0.0,
@@ -142,6 +285,9 @@ impl Default for App {
                                 z: 0.0,
                             },
                             layer: 0.0,
+                            label: None,
+                            point_style: PointStyle::Disc,
+                            z_order: 0,
                         }),
                     },
                 ),
@@ -149,6 +295,8 @@ impl Default for App {
                     "e12".into(),
                     Variable {
                         value: Multivector::ZERO,
+                        frozen: None,
+                        group: None,
                         display: Some(VariableDisplay {
                             color: cgmath::Vector3 {
                                 x: 1.0,
@@ -156,10 +304,65 @@ impl Default for App {
                                 z: 1.0,
                             },
                             layer: 0.01,
+                            label: None,
+                            point_style: PointStyle::Disc,
+                            z_order: 0,
                         }),
                     },
                 ),
             ]),
+            clean_values: false,
+            display_defaults: DisplayDefaults::default(),
+            hidden_groups: BTreeSet::new(),
+            expanded_parameters: BTreeSet::new(),
+            expanded_variables: BTreeSet::new(),
+            pinned_variables: vec![],
+            intersection_lines: (None, None),
+            join_points: (None, None),
+            joins: vec![],
+            orbit_selection: (None, None),
+            transform_all_motor: None,
+            orbits: vec![],
+            polygon_points: String::new(),
+            polygons: vec![],
+            measure_selection: (None, None),
+            measurement: None,
+            product_selection: (None, None),
+            product_decomposition: None,
+            dragged_parameter: None,
+            gizmo_drag_start: None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Display)]
+#[display("{location}: {message}")]
+pub struct Diagnostic {
+    pub location: Location,
+    /// Where the problem ends if it covers a known span, otherwise the rest of the line is highlighted
+    pub end: Option<Location>,
+    pub message: String,
+}
+
+impl From<ParseError<'_>> for Diagnostic {
+    fn from(error: ParseError<'_>) -> Self {
+        Diagnostic {
+            location: error.location,
+            end: match &error.kind {
+                ParseErrorKind::UnexpectedToken(token) => Some(token.end),
+                _ => None,
+            },
+            message: error.kind.to_string(),
+        }
+    }
+}
+
+impl From<EvaluationError<'_>> for Diagnostic {
+    fn from(error: EvaluationError<'_>) -> Self {
+        Diagnostic {
+            location: error.location,
+            end: None,
+            message: error.kind.to_string(),
         }
     }
 }
@@ -168,476 +371,4837 @@ impl Default for App {
 pub struct Variable {
     #[serde(default, skip)]
     pub value: Multivector,
+    /// A snapshot of the value that keeps being rendered while the code changes the live value
+    #[serde(skip)]
+    pub frozen: Option<Multivector>,
     pub display: Option<VariableDisplay>,
+    #[serde(default)]
+    pub group: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct VariableDisplay {
     pub color: cgmath::Vector3<f32>,
     pub layer: f32,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub point_style: PointStyle,
+    /// Objects with a larger z order are drawn on top, ones with the same z order are drawn by layer
+    #[serde(default)]
+    pub z_order: i32,
+}
+
+/// What a variable's display starts as when it is turned on
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+struct DisplayDefaults {
+    color: cgmath::Vector3<f32>,
+    layer: f32,
+}
+
+impl Default for DisplayDefaults {
+    fn default() -> Self {
+        Self {
+            color: cgmath::Vector3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            layer: 0.05,
+        }
+    }
+}
+
+impl DisplayDefaults {
+    fn display(&self) -> VariableDisplay {
+        VariableDisplay {
+            color: self.color,
+            layer: self.layer,
+            label: None,
+            point_style: PointStyle::Disc,
+            z_order: 0,
+        }
+    }
+}
+
+/// How the point part of a displayed variable is drawn, the discriminants are what the shader expects
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PointStyle {
+    #[default]
+    Disc = 0,
+    Ring = 1,
+    Cross = 2,
+    Square = 3,
+}
+
+impl PointStyle {
+    pub fn display_name(&self) -> &'static str {
+        match *self {
+            PointStyle::Disc => "Disc",
+            PointStyle::Ring => "Ring",
+            PointStyle::Cross => "Cross",
+            PointStyle::Square => "Square",
+        }
+    }
+}
+
+/// The parts of the scene as they were when it was loaded, used to show what has changed during the session.
+/// Saving does not replace it, since auto-save would otherwise keep clearing the changes
+struct SavedSnapshot {
+    parameters: Vec<(String, Multivector)>,
+    variables: BTreeSet<String>,
+    code: String,
+}
+
+impl SavedSnapshot {
+    fn new(app: &App) -> Self {
+        Self {
+            parameters: app
+                .parameters
+                .iter()
+                .map(|parameter| (parameter.name.clone(), parameter.value))
+                .collect(),
+            variables: app.variables.keys().cloned().collect(),
+            code: app.code.clone(),
+        }
+    }
+
+    /// A line for every parameter component that differs, every added or removed parameter
+    /// and variable, and one for the code if it differs at all
+    fn changes(
+        &self,
+        parameters: &[Parameter],
+        variables: &BTreeMap<String, Variable>,
+        code: &str,
+    ) -> Vec<String> {
+        let mut changes = vec![];
+        for (name, old_value) in &self.parameters {
+            match parameters.iter().find(|parameter| &parameter.name == name) {
+                Some(parameter) => {
+                    for ((component, old), (_, new)) in old_value
+                        .components()
+                        .into_iter()
+                        .zip(parameter.value.components())
+                    {
+                        if old != new {
+                            changes.push(format!("{name}.{component}: {old} → {new}"));
+                        }
+                    }
+                }
+                None => changes.push(format!("Removed parameter {name}")),
+            }
+        }
+        for parameter in parameters {
+            if !self
+                .parameters
+                .iter()
+                .any(|(name, _)| name == &parameter.name)
+            {
+                changes.push(format!("Added parameter {}", parameter.name));
+            }
+        }
+
+        for name in variables.keys() {
+            if !self.variables.contains(name) {
+                changes.push(format!("Added variable {name}"));
+            }
+        }
+        for name in &self.variables {
+            if !variables.contains_key(name) {
+                changes.push(format!("Removed variable {name}"));
+            }
+        }
+
+        if self.code != code {
+            changes.push("Code changed".into());
+        }
+        changes
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Orbit {
+    motor: String,
+    point: String,
+    samples: usize,
+    color: cgmath::Vector3<f32>,
+}
+
+impl Orbit {
+    /// The point after applying the motor 0, 1, 2, ... times
+    fn points(&self, variables: &BTreeMap<String, Variable>) -> Vec<Multivector> {
+        let (Some(motor), Some(point)) = (variables.get(&self.motor), variables.get(&self.point))
+        else {
+            return vec![];
+        };
+        let motor = (motor.value.grade0() + motor.value.grade2()).normalized();
+        let mut point = point.value.grade2();
+        (0..self.samples)
+            .map(|_| {
+                let current = point;
+                point = motor * point * motor.reverse();
+                current
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Polygon {
+    points: Vec<String>,
+    color: cgmath::Vector3<f32>,
+    opacity: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Join {
+    first: String,
+    second: String,
+    color: cgmath::Vector3<f32>,
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 struct Camera {
     transform: Multivector,
     view_height: f32,
+    max_view_height: f32,
     move_speed: f32,
     zoom_speed: f32,
     line_thickness: f32,
     point_radius: f32,
+    outlines: bool,
+    outline_width: f32,
+    line_extent: f32,
+    layer_badges: bool,
+    /// Shows a badge on displayed objects that are drawn on top of each other
+    coincident_badges: bool,
+    /// Darkens the half of each line on the opposite side to its normal, so `l` and `-l` look different
+    line_orientation: bool,
+    /// Moves a dragged point exactly onto a displayed point when it gets close to one
+    snap_to_points: bool,
+    gizmo: bool,
+    show_weight: bool,
+    ideal_line: bool,
+    /// The increment in radians that gizmo rotations snap to while holding shift
+    snap_angle: f32,
+    /// A motor variable that the camera transform follows every frame
+    driver: Option<String>,
+    /// How many seconds the camera takes to mostly catch up to its driver, 0 to follow it exactly
+    driver_smoothing: f32,
+    /// Continuously rotates the camera around the middle of the view
+    spin: bool,
+    /// How fast the camera spins in radians per second, counterclockwise
+    spin_speed: f32,
 }
 
-#[derive(Serialize, Deserialize)]
-struct Parameter {
-    name: String,
-    type_: ParameterType,
-    value: Multivector,
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            transform: Multivector {
+                s: 1.0,
+                ..Multivector::ZERO
+            },
+            view_height: 10.0,
+            max_view_height: 10000.0,
+            move_speed: 1.0,
+            zoom_speed: 2.0,
+            line_thickness: 0.1,
+            point_radius: 0.1,
+            outlines: false,
+            outline_width: 2.0,
+            line_extent: 0.0,
+            layer_badges: false,
+            coincident_badges: true,
+            line_orientation: false,
+            snap_to_points: true,
+            gizmo: false,
+            show_weight: false,
+            ideal_line: false,
+            snap_angle: 15f32.to_radians(),
+            driver: None,
+            driver_smoothing: 0.0,
+            spin: false,
+            spin_speed: 15f32.to_radians(),
+        }
+    }
+}
+
+/// The draggable parts of the camera gizmo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GizmoHandle {
+    TranslateX,
+    TranslateY,
+    Rotate,
+}
+
+impl GizmoHandle {
+    /// Where the handle is drawn relative to the middle of the view, in pixels
+    fn offset(self) -> egui::Vec2 {
+        match self {
+            GizmoHandle::TranslateX => egui::vec2(60.0, 0.0),
+            GizmoHandle::TranslateY => egui::vec2(0.0, -60.0),
+            GizmoHandle::Rotate => egui::vec2(40.0, -40.0),
+        }
+    }
+
+    fn color(self) -> egui::Color32 {
+        match self {
+            GizmoHandle::TranslateX => egui::Color32::RED,
+            GizmoHandle::TranslateY => egui::Color32::GREEN,
+            GizmoHandle::Rotate => egui::Color32::LIGHT_BLUE,
+        }
+    }
+
+    /// The motor in camera space for dragging this handle from `previous` to `current`,
+    /// the arrows move the camera along their axis and the circle rotates it by the angle swept around `center`
+    fn motor(
+        self,
+        center: egui::Pos2,
+        previous: egui::Pos2,
+        current: egui::Pos2,
+        units_per_pixel: f32,
+    ) -> Multivector {
+        // screen y points down but camera y points up
+        let (x, y) = match self {
+            GizmoHandle::TranslateX => ((current.x - previous.x) * units_per_pixel, 0.0),
+            GizmoHandle::TranslateY => (0.0, (previous.y - current.y) * units_per_pixel),
+            GizmoHandle::Rotate => {
+                return Multivector::rotor(swept_angle(center, previous, current));
+            }
+        };
+        let inf_point = Multivector {
+            e1: x,
+            e2: y,
+            ..Multivector::ZERO
+        }
+        .wedge(Multivector {
+            e0: 1.0,
+            ..Multivector::ZERO
+        });
+        Multivector::exp(inf_point * 0.5)
+    }
+}
+
+/// The counterclockwise angle swept around `center` when moving from `from` to `to` on the screen
+fn swept_angle(center: egui::Pos2, from: egui::Pos2, to: egui::Pos2) -> f32 {
+    // screen y points down
+    let angle = |position: egui::Pos2| (center.y - position.y).atan2(position.x - center.x);
+    angle(to) - angle(from)
+}
+
+/// Rounds `angle` to the nearest multiple of `increment`, or leaves it alone if there is no increment
+fn snap_angle(angle: f32, increment: f32) -> f32 {
+    if increment > 0.0 {
+        (angle / increment).round() * increment
+    } else {
+        angle
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-enum ParameterType {
-    Grade0,
-    Grade1,
-    Grade2,
-    Grade3,
-    Multivector,
+pub enum AngleUnit {
+    Radians,
+    Degrees,
 }
 
-impl ParameterType {
+impl AngleUnit {
     pub fn display_name(&self) -> &'static str {
         match *self {
-            ParameterType::Grade0 => "Scalar",
-            ParameterType::Grade1 => "Grade 1",
-            ParameterType::Grade2 => "Grade 2",
-            ParameterType::Grade3 => "Grade 3",
-            ParameterType::Multivector => "Multivector",
+            AngleUnit::Radians => "Radians",
+            AngleUnit::Degrees => "Degrees",
+        }
+    }
+
+    pub fn to_radians(&self, angle: f32) -> f32 {
+        match *self {
+            AngleUnit::Radians => angle,
+            AngleUnit::Degrees => angle.to_radians(),
+        }
+    }
+
+    pub fn from_radians(&self, radians: f32) -> f32 {
+        match *self {
+            AngleUnit::Radians => radians,
+            AngleUnit::Degrees => radians.to_degrees(),
+        }
+    }
+
+    pub fn format(&self, radians: f32) -> String {
+        match *self {
+            AngleUnit::Radians => format!("{radians:.3} rad"),
+            AngleUnit::Degrees => format!("{:.3}°", radians.to_degrees()),
         }
     }
 }
 
-impl App {
-    fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let renderer = cc.wgpu_render_state.as_ref().unwrap();
-        let state = RenderState::new(renderer.target_format, &renderer.device, &renderer.queue);
-        renderer.renderer.write().callback_resources.insert(state);
+#[derive(Serialize, Deserialize)]
+struct Driver {
+    name: String,
+    value: f32,
+    min: f32,
+    max: f32,
+    step: f32,
+}
+
+/// The most samples a sweep can take, since every sample runs the whole script
+const MAX_SWEEP_SAMPLES: usize = 10_000;
 
-        let storage = cc.storage.unwrap();
+/// A summary of the values a scalar variable took while a driver was swept over its range
+struct SweepStatistics {
+    min: f32,
+    max: f32,
+    mean: f32,
+    /// How many samples fell into each of the equal width bins between `min` and `max`
+    histogram: Vec<usize>,
+    /// How many samples failed to evaluate or were not finite, which are left out of everything else
+    skipped: usize,
+}
+
+impl SweepStatistics {
+    const BINS: usize = 20;
 
-        let flavour = storage
-            .get_string("Flavour")
-            .and_then(|s| ron::from_str(&s).ok())
-            .unwrap_or_default();
-        GA_FLAVOUR.store(flavour, Ordering::Relaxed);
+    /// Returns `None` if none of the samples are finite
+    fn new(samples: &[Option<f32>]) -> Option<Self> {
+        let values = samples
+            .iter()
+            .filter_map(|&sample| sample.filter(|value| value.is_finite()))
+            .collect::<Vec<_>>();
+        if values.is_empty() {
+            return None;
+        }
 
-        storage
-            .get_string("App")
-            .and_then(|s| ron::from_str(&s).ok())
-            .unwrap_or_default()
+        let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let mean = values.iter().map(|&value| value as f64).sum::<f64>() / values.len() as f64;
+        let mut histogram = vec![0; Self::BINS];
+        for value in &values {
+            let bin = if max > min {
+                ((value - min) / (max - min) * Self::BINS as f32) as usize
+            } else {
+                0
+            };
+            histogram[bin.min(Self::BINS - 1)] += 1;
+        }
+        Some(Self {
+            min,
+            max,
+            mean: mean as f32,
+            histogram,
+            skipped: samples.len() - values.len(),
+        })
     }
+}
 
-    fn update_code(&mut self) {
-        let mut assigned_variables = HashSet::new();
+/// The most frames that can be baked at once, since every frame runs the whole script
+const MAX_BAKE_FRAMES: usize = 10_000;
 
-        for parameter in &self.parameters {
-            self.variables
-                .entry(parameter.name.clone())
-                .or_insert_with(|| Variable {
-                    value: Multivector::ZERO,
-                    display: None,
-                })
-                .value = parameter.value;
-            assigned_variables.insert(parameter.name.as_str());
+/// Settings for writing the animation out as a numbered sequence of svg frames
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct Bake {
+    folder: String,
+    frames: usize,
+    /// How much animation time the frames cover, they are spaced evenly starting at 0
+    duration: f32,
+}
+
+impl Default for Bake {
+    fn default() -> Self {
+        Self {
+            folder: "frames".into(),
+            frames: 60,
+            duration: 2.0,
         }
+    }
+}
 
-        self.errors.clear();
-        'evaluation: {
-            let statements = match parse(&self.code) {
-                Ok(statements) => statements,
-                Err(error) => {
-                    self.errors.push(format!("{error}"));
-                    break 'evaluation;
-                }
-            };
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct Animation {
+    playing: bool,
+    time: f32,
+    speed: f32,
+    duration: f32,
+    mode: PlaybackMode,
+}
 
-            for statement in statements {
-                match statement.kind {
-                    AstStatementKind::Assignment {
-                        name,
-                        name_token: _,
-                        equals_token: _,
-                        value,
-                    } => {
-                        let value = match evaluate_expression(&value, &self.variables) {
-                            Ok(value) => value,
-                            Err(error) => {
-                                self.errors.push(error);
-                                continue;
-                            }
-                        };
-                        self.variables
-                            .entry(name.into())
-                            .or_insert_with(|| Variable {
-                                value: Multivector::ZERO,
-                                display: None,
-                            })
-                            .value = value;
-                        assigned_variables.insert(name);
-                    }
-                }
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            playing: false,
+            time: 0.0,
+            speed: 1.0,
+            duration: 5.0,
+            mode: PlaybackMode::Loop,
+        }
+    }
+}
+
+impl Animation {
+    fn advance(&mut self, dt: f32) {
+        if !self.playing {
+            return;
+        }
+        self.time += dt * self.speed;
+        if self.mode == PlaybackMode::Once && self.duration > 0.0 {
+            if self.time >= self.duration && self.speed >= 0.0 {
+                self.time = self.duration;
+                self.playing = false;
+            } else if self.time <= 0.0 && self.speed < 0.0 {
+                self.time = 0.0;
+                self.playing = false;
             }
         }
+    }
 
-        if self.errors.is_empty() {
-            self.variables
-                .retain(|variable_name, _| assigned_variables.contains(variable_name.as_str()));
+    fn phase(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return self.time;
+        }
+        match self.mode {
+            PlaybackMode::Loop => self.time.rem_euclid(self.duration),
+            PlaybackMode::PingPong => {
+                let phase = self.time.rem_euclid(2.0 * self.duration);
+                if phase > self.duration {
+                    2.0 * self.duration - phase
+                } else {
+                    phase
+                }
+            }
+            PlaybackMode::Once => self.time.clamp(0.0, self.duration),
         }
     }
 }
 
-impl eframe::App for App {
-    fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
-        let mut code_or_parameters_changed = self.last_time.is_none(); // hacky way to detect first time code has run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Theme {
+    Dark,
+    Light,
+}
 
-        let time = std::time::Instant::now();
-        let dt = (time - self.last_time.unwrap_or(time)).as_secs_f32();
-        self.last_time = Some(time);
+impl Theme {
+    pub fn display_name(&self) -> &'static str {
+        match *self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+        }
+    }
 
-        egui::TopBottomPanel::top("Menu").show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                self.info_window_open |= ui.button("Info").clicked();
-                self.camera_window_open |= ui.button("Camera").clicked();
-                self.parameters_window_open |= ui.button("Parameters").clicked();
-                self.code_window_open |= ui.button("Code").clicked();
-                self.variables_window_open |= ui.button("Variables Window").clicked();
-            });
-        });
+    pub fn visuals(&self) -> egui::Visuals {
+        match *self {
+            Theme::Dark => egui::Visuals::dark(),
+            Theme::Light => egui::Visuals::light(),
+        }
+    }
 
-        {
-            let mut reset_everything = false;
-            egui::Window::new("Info")
-                .open(&mut self.info_window_open)
-                .resizable(false)
-                .show(ctx, |ui| {
-                    ui.label(format!("FPS: {:.3}", 1.0 / dt));
-                    ui.label(format!("Frame Time: {:.3}ms", 1000.0 * dt));
-                    reset_everything |= ui.button("RESET EVERYTHING").clicked();
-                });
-            if reset_everything {
-                *self = Self::default();
-                GA_FLAVOUR.store(0, Ordering::Relaxed);
-                return;
-            }
+    /// The color behind the rendered objects
+    pub fn background(&self) -> egui::Color32 {
+        match *self {
+            Theme::Dark => egui::Color32::from_rgb(50, 50, 50),
+            Theme::Light => egui::Color32::from_rgb(235, 235, 235),
         }
+    }
 
-        egui::Window::new("Camera")
-            .open(&mut self.camera_window_open)
-            .resizable(false)
-            .show(ctx, |ui| {
-                ui.collapsing("Transform", |ui| {
-                    edit_multivector(ui, &mut self.camera.transform, true, true, true, true);
-                });
-                if ui.button("Normalize Transform").clicked() {
-                    self.camera.transform = self.camera.transform.normalized();
-                }
-                ui.horizontal(|ui| {
-                    ui.label("View Height:");
-                    ui.add(egui::DragValue::new(&mut self.camera.view_height).speed(0.1));
-                });
+    /// The color for text and handles drawn over the view
+    pub fn overlay_color(&self) -> egui::Color32 {
+        match *self {
+            Theme::Dark => egui::Color32::WHITE,
+            Theme::Light => egui::Color32::BLACK,
+        }
+    }
+
+    /// A color for new objects that stands out against the background
+    pub fn default_object_color(&self) -> cgmath::Vector3<f32> {
+        match *self {
+            Theme::Dark => cgmath::Vector3 {
+                x: 1.0,
+                y: 1.0,
+                z: 1.0,
+            },
+            Theme::Light => cgmath::Vector3 {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        }
+    }
+}
+
+/// The product shown in the product table window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum TableProduct {
+    Geometric,
+    Wedge,
+    Inner,
+}
+
+impl TableProduct {
+    pub fn display_name(&self) -> &'static str {
+        match *self {
+            TableProduct::Geometric => "Geometric",
+            TableProduct::Wedge => "Wedge",
+            TableProduct::Inner => "Inner",
+        }
+    }
+
+    /// The operator for this product in the code
+    pub fn operator(&self) -> &'static str {
+        match *self {
+            TableProduct::Geometric => "*",
+            TableProduct::Wedge => "^",
+            TableProduct::Inner => "|",
+        }
+    }
+
+    pub fn apply(&self, left: Multivector, right: Multivector) -> Multivector {
+        match *self {
+            TableProduct::Geometric => left * right,
+            TableProduct::Wedge => left.wedge(right),
+            TableProduct::Inner => left.inner(right),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum PlaybackMode {
+    Loop,
+    PingPong,
+    Once,
+}
+
+impl PlaybackMode {
+    pub fn display_name(&self) -> &'static str {
+        match *self {
+            PlaybackMode::Loop => "Loop",
+            PlaybackMode::PingPong => "Ping-Pong",
+            PlaybackMode::Once => "Once",
+        }
+    }
+}
+
+/// The most actions that can be undone, older ones are forgotten
+const MAX_UNDO: usize = 32;
+
+/// An action that throws away a lot at once, so it is confirmed first and can be undone
+#[derive(Clone, Copy)]
+enum ClearAction {
+    Parameters,
+    Displays,
+}
+
+impl ClearAction {
+    pub fn display_name(&self) -> &'static str {
+        match *self {
+            ClearAction::Parameters => "Clear Parameters",
+            ClearAction::Displays => "Hide All Displays",
+        }
+    }
+
+    pub fn confirmation(&self) -> &'static str {
+        match *self {
+            ClearAction::Parameters => "Delete every parameter?",
+            ClearAction::Displays => {
+                "Stop displaying every variable, forgetting their colors and layers?"
+            }
+        }
+    }
+}
+
+/// What is needed to put things back after a [`ClearAction`]
+enum UndoEntry {
+    Parameters(Vec<Parameter>),
+    Displays(Vec<(String, VariableDisplay)>),
+}
+
+impl UndoEntry {
+    pub fn action(&self) -> ClearAction {
+        match *self {
+            UndoEntry::Parameters(_) => ClearAction::Parameters,
+            UndoEntry::Displays(_) => ClearAction::Displays,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Parameter {
+    name: String,
+    type_: ParameterType,
+    value: Multivector,
+    /// Renormalises the value after every edit, for parameters that are rotors or motors
+    #[serde(default)]
+    keep_normalized: bool,
+    /// Edits grade 1 values with a magnitude and angle for `e1` and `e2` instead, the value is stored the same way
+    #[serde(default)]
+    polar: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum ParameterType {
+    Grade0,
+    Grade1,
+    Grade2,
+    Grade3,
+    Multivector,
+}
+
+impl ParameterType {
+    pub fn display_name(&self) -> &'static str {
+        match *self {
+            ParameterType::Grade0 => "Scalar",
+            ParameterType::Grade1 => "Grade 1",
+            ParameterType::Grade2 => "Grade 2",
+            ParameterType::Grade3 => "Grade 3",
+            ParameterType::Multivector => "Multivector",
+        }
+    }
+}
+
+impl App {
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let gpu_error = match cc.wgpu_render_state.as_ref() {
+            Some(renderer) => {
+                match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    RenderState::new(renderer.target_format, &renderer.device, &renderer.queue)
+                })) {
+                    Ok(state) => {
+                        renderer.renderer.write().callback_resources.insert(state);
+                        None
+                    }
+                    Err(_) => {
+                        Some("Failed to create the GPU resources needed for rendering".into())
+                    }
+                }
+            }
+            None => Some("No wgpu render state is available on this backend".into()),
+        };
+
+        let mut app: Self = match cc.storage {
+            Some(storage) => {
+                let flavour = storage
+                    .get_string("Flavour")
+                    .and_then(|s| ron::from_str(&s).ok())
+                    .unwrap_or_default();
+                GA_FLAVOUR.store(flavour, Ordering::Relaxed);
+                let flipped_orientation = storage
+                    .get_string("FlippedOrientation")
+                    .and_then(|s| ron::from_str(&s).ok())
+                    .unwrap_or_default();
+                FLIPPED_ORIENTATION.store(flipped_orientation, Ordering::Relaxed);
+
+                storage
+                    .get_string("App")
+                    .and_then(|s| ron::from_str(&s).ok())
+                    .unwrap_or_default()
+            }
+            None => Self::default(),
+        };
+        #[cfg(target_arch = "wasm32")]
+        app.apply_query_overrides(&cc.integration_info.web_info.location.query_map);
+        app.gpu_error = gpu_error;
+        if cc.storage.is_some() {
+            app.saved_snapshot = Some(SavedSnapshot::new(&app));
+        }
+        app
+    }
+
+    /// Applies the overrides from a url query string, so links to the web build can open a specific scene.
+    /// `scene` replaces everything with a saved app, `code` replaces the code, and any other key sets the
+    /// parameter or driver with that name to the value of an expression, keys that do not match anything are ignored
+    #[cfg_attr(not(target_arch = "wasm32"), expect(dead_code))]
+    fn apply_query_overrides(&mut self, query: &BTreeMap<String, Vec<String>>) {
+        // a key given more than once uses its last value, like most websites
+        let value_of = |key: &str| query.get(key).and_then(|values| values.last());
+
+        if let Some(scene) = value_of("scene")
+            && let Ok(scene) = ron::from_str(scene)
+        {
+            *self = scene;
+        }
+        if let Some(code) = value_of("code") {
+            self.code = code.clone();
+        }
+        for parameter in &mut self.parameters {
+            if let Some(value) =
+                value_of(&parameter.name).and_then(|value| parse_multivector_expression(value))
+            {
+                parameter.value = match parameter.type_ {
+                    ParameterType::Grade0 => value.grade0(),
+                    ParameterType::Grade1 => value.grade1(),
+                    ParameterType::Grade2 => value.grade2(),
+                    ParameterType::Grade3 => value.grade3(),
+                    ParameterType::Multivector => value,
+                };
+                if parameter.keep_normalized {
+                    parameter.value = parameter.value.normalized();
+                }
+            }
+        }
+        for driver in &mut self.drivers {
+            if let Some(value) =
+                value_of(&driver.name).and_then(|value| parse_scalar_expression(value))
+            {
+                driver.value = value as f32;
+            }
+        }
+    }
+
+    fn unique_variable_name(&self, prefix: &str) -> String {
+        if !self.variables.contains_key(prefix) {
+            return prefix.into();
+        }
+        (1..)
+            .map(|i| format!("{prefix}_{i}"))
+            .find(|name| !self.variables.contains_key(name))
+            .unwrap()
+    }
+
+    fn update_code(&mut self) {
+        self.variables
+            .entry(TIME_VARIABLE.into())
+            .or_insert_with(|| Variable {
+                value: Multivector::ZERO,
+                frozen: None,
+                group: None,
+                display: None,
+            })
+            .value = Multivector {
+            s: self.animation.phase(),
+            ..Multivector::ZERO
+        };
+
+        // drivers and parameters named `t` are outlined as invalid, they would overwrite the time
+        for driver in self
+            .drivers
+            .iter()
+            .filter(|driver| driver.name != TIME_VARIABLE)
+        {
+            self.variables
+                .entry(driver.name.clone())
+                .or_insert_with(|| Variable {
+                    value: Multivector::ZERO,
+                    frozen: None,
+                    group: None,
+                    display: None,
+                })
+                .value = Multivector {
+                s: driver.value,
+                ..Multivector::ZERO
+            };
+        }
+
+        for parameter in self
+            .parameters
+            .iter()
+            .filter(|parameter| parameter.name != TIME_VARIABLE)
+        {
+            self.variables
+                .entry(parameter.name.clone())
+                .or_insert_with(|| Variable {
+                    value: Multivector::ZERO,
+                    frozen: None,
+                    group: None,
+                    display: None,
+                })
+                .value = parameter.value;
+        }
+        let mut assigned_variables = input_names(&self.drivers, &self.parameters);
+
+        self.errors.clear();
+        self.selected_error = None;
+        self.warnings.clear();
+        // a statement that is still being typed is skipped instead of being an error,
+        // but nothing can be removed since it may assign variables that are in use
+        let mut incomplete = false;
+        'evaluation: {
+            let statements = match parse_lenient(&self.code) {
+                Ok((statements, incomplete_location)) => {
+                    incomplete = incomplete_location.is_some();
+                    statements
+                }
+                Err(error) => {
+                    self.errors.push(error.into());
+                    break 'evaluation;
+                }
+            };
+
+            if self.warn_unused_variables {
+                let mut read_names = HashSet::new();
+                for statement in &statements {
+                    match &statement.kind {
+                        AstStatementKind::Assignment { value, .. } => {
+                            value.for_each_name(&mut |name, _| {
+                                read_names.insert(name);
+                            });
+                        }
+                    }
+                }
+                for statement in &statements {
+                    match statement.kind {
+                        AstStatementKind::Assignment {
+                            name,
+                            ref name_token,
+                            ..
+                        } => {
+                            let displayed = self
+                                .variables
+                                .get(name)
+                                .is_some_and(|variable| variable.display.is_some());
+                            let pinned = self.pinned_variables.iter().any(|pinned| pinned == name);
+                            if !read_names.contains(name) && !displayed && !pinned {
+                                self.warnings.push(Diagnostic {
+                                    location: name_token.location,
+                                    end: Some(name_token.end),
+                                    message: format!("Variable '{name}' is computed but unused"),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+
+            let settings = self.script_settings();
+            let parallel_outcomes = if self.parallel_evaluation {
+                evaluate_in_parallel(
+                    &statements,
+                    &mut self.variables,
+                    &mut assigned_variables,
+                    settings,
+                )
+            } else {
+                None
+            };
+            let outcomes = parallel_outcomes.unwrap_or_else(|| {
+                evaluate_statements(
+                    &statements,
+                    &mut self.variables,
+                    &mut assigned_variables,
+                    settings,
+                )
+            });
+            for outcome in outcomes {
+                self.warnings.extend(outcome.warnings);
+                match outcome.result {
+                    StatementResult::Value(_) | StatementResult::Skipped => {}
+                    StatementResult::Error(error) | StatementResult::OutOfBudget(error) => {
+                        self.errors.push(error);
+                    }
+                }
+            }
+        }
+
+        if self.errors.is_empty() && !incomplete {
+            self.variables
+                .retain(|variable_name, _| assigned_variables.contains(variable_name.as_str()));
+            self.pinned_variables
+                .retain(|variable_name| assigned_variables.contains(variable_name.as_str()));
+        }
+    }
+
+    /// Steps the animation time evenly across the bake duration, running the code and writing the view
+    /// as an svg for each frame, then puts the time back. Returns a summary, or the first error
+    fn bake_frames(&mut self, rect: egui::Rect) -> Result<String, String> {
+        let folder = std::path::PathBuf::from(&self.bake.folder);
+        std::fs::create_dir_all(&folder)
+            .map_err(|error| format!("Could not create '{}': {error}", folder.display()))?;
+
+        let frames = self.bake.frames.clamp(1, MAX_BAKE_FRAMES);
+        let time = self.animation.time;
+        let mut result = Ok(());
+        for frame in 0..frames {
+            self.animation.time = self.bake.duration * frame as f32 / frames as f32;
+            self.update_code();
+            let path = folder.join(format!("frame_{frame:04}.svg"));
+            if let Err(error) = std::fs::write(&path, scene_to_svg(self, rect)) {
+                result = Err(format!("Could not write '{}': {error}", path.display()));
+                break;
+            }
+        }
+        self.animation.time = time;
+        self.update_code();
+
+        result.map(|()| format!("Wrote {frames} frames to '{}'", folder.display()))
+    }
+
+    /// Runs the whole code once for each of `samples` values of `driver` spread evenly over its range,
+    /// in a scratch copy of the current variables, returning the scalar part of `variable` after each run
+    fn sample_sweep(&self, driver: &str, variable: &str, samples: usize) -> Vec<Option<f32>> {
+        let Some(driver) = self.drivers.iter().find(|d| d.name == driver) else {
+            return vec![];
+        };
+        let Ok((statements, _)) = parse_lenient(&self.code) else {
+            return vec![];
+        };
+        let settings = self.script_settings();
+
+        (0..samples)
+            .map(|i| {
+                let amount = if samples > 1 {
+                    i as f32 / (samples - 1) as f32
+                } else {
+                    0.5
+                };
+                let mut scope = self.scratch_scope();
+                assign(
+                    &mut scope,
+                    &driver.name,
+                    Multivector {
+                        s: driver.min + (driver.max - driver.min) * amount,
+                        ..Multivector::ZERO
+                    },
+                );
+                let mut assigned = input_names(&self.drivers, &self.parameters);
+                evaluate_statements(&statements, &mut scope, &mut assigned, settings);
+                assigned
+                    .contains(variable)
+                    .then(|| scope[variable].value.scalar())
+            })
+            .collect()
+    }
+
+    /// A copy of the current values without any display settings, to evaluate into without changing them
+    fn scratch_scope(&self) -> BTreeMap<String, Variable> {
+        self.variables
+            .iter()
+            .map(|(name, variable)| {
+                (
+                    name.clone(),
+                    Variable {
+                        value: variable.value,
+                        frozen: None,
+                        group: None,
+                        display: None,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// The settings from the Code window that scripts are evaluated with
+    fn script_settings(&self) -> ScriptSettings {
+        ScriptSettings {
+            angle_unit: if self.script_angle_unit {
+                self.angle_unit
+            } else {
+                AngleUnit::Radians
+            },
+            strict_scalars: self.strict_scalar_arguments,
+            budget: self.evaluation_budget,
+        }
+    }
+
+    /// Runs the statements in `self.code[selection]` in a scratch copy of the current variables,
+    /// returning a line of output per statement without changing any variables
+    fn evaluate_selection(&self, selection: std::ops::Range<usize>) -> Vec<String> {
+        let base = location_at(&self.code, selection.start);
+
+        let statements = match parse(&self.code[selection]) {
+            Ok(statements) => statements,
+            Err(error) => {
+                return vec![format!(
+                    "{}: Selection is not a list of complete statements: {}",
+                    offset_location(base, error.location),
+                    error.kind
+                )];
+            }
+        };
+        if statements.is_empty() {
+            return vec!["Selection has no statements".into()];
+        }
+
+        let mut scope = self.scratch_scope();
+        // a selection can read anything the last evaluation computed
+        let mut assigned = self.variables.keys().map(String::as_str).collect();
+        let outcomes = evaluate_statements(
+            &statements,
+            &mut scope,
+            &mut assigned,
+            self.script_settings(),
+        );
+        let mut output = vec![];
+        for (statement, outcome) in statements.iter().zip(outcomes) {
+            for warning in outcome.warnings {
+                output.push(format!(
+                    "{}: Warning: {}",
+                    offset_location(base, warning.location),
+                    warning.message
+                ));
+            }
+            match outcome.result {
+                StatementResult::Value(value) => match statement.kind {
+                    AstStatementKind::Assignment { name, .. } => {
+                        output.push(format!("{name} = {value}"));
+                    }
+                },
+                StatementResult::Error(error) | StatementResult::OutOfBudget(error) => {
+                    output.push(format!(
+                        "{}: {}",
+                        offset_location(base, error.location),
+                        error.message
+                    ))
+                }
+                StatementResult::Skipped => {}
+            }
+        }
+        output
+    }
+}
+
+impl eframe::App for App {
+    fn update(&mut self, ctx: &eframe::egui::Context, frame: &mut eframe::Frame) {
+        let mut pending_evaluation = PendingEvaluation::default();
+        pending_evaluation |= self.last_time.is_none(); // hacky way to detect first time code has run
+
+        let time = std::time::Instant::now();
+        let dt = (time - self.last_time.unwrap_or(time)).as_secs_f32();
+        self.last_time = Some(time);
+
+        if ctx.style().visuals.dark_mode != (self.theme == Theme::Dark) {
+            ctx.set_visuals(self.theme.visuals());
+        }
+
+        if let Some(gpu_error) = &self.gpu_error {
+            egui::Window::new("GPU Unavailable")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(egui::RichText::new(gpu_error).color(egui::Color32::RED));
+                    ui.label("Objects will not be rendered, but scripts can still be edited.");
+                });
+        }
+
+        let undo_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+        let mut undo = false;
+        egui::TopBottomPanel::top("Menu").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                self.info_window_open |= ui.button("Info").on_hover_text("F1").clicked();
+                self.camera_window_open |= ui.button("Camera").on_hover_text("F2").clicked();
+                self.parameters_window_open |=
+                    ui.button("Parameters").on_hover_text("F3").clicked();
+                self.drivers_window_open |= ui.button("Drivers").on_hover_text("F6").clicked();
+                self.animation_window_open |= ui.button("Animation").on_hover_text("F7").clicked();
+                self.code_window_open |= ui.button("Code").on_hover_text("F4").clicked();
+                self.variables_window_open |=
+                    ui.button("Variables Window").on_hover_text("F5").clicked();
+                self.changes_window_open |= ui.button("Changes").on_hover_text("F9").clicked();
+                self.product_table_window_open |=
+                    ui.button("Product Table").on_hover_text("F10").clicked();
+                ui.separator();
+                let undo_text = match self.undo_stack.last() {
+                    Some(entry) => format!("Undo {}", entry.action().display_name()),
+                    None => "Undo".into(),
+                };
+                undo |= ui
+                    .add_enabled(!self.undo_stack.is_empty(), egui::Button::new(undo_text))
+                    .on_hover_text(ui.ctx().format_shortcut(&undo_shortcut))
+                    .clicked();
+            });
+        });
+
+        if let Some(action) = self.pending_clear {
+            egui::Window::new(action.display_name())
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label(action.confirmation());
+                    ui.label("This can be undone.");
+                    ui.horizontal(|ui| {
+                        if ui.button(action.display_name()).clicked() {
+                            let entry = match action {
+                                ClearAction::Parameters => {
+                                    pending_evaluation |= true;
+                                    UndoEntry::Parameters(std::mem::take(&mut self.parameters))
+                                }
+                                ClearAction::Displays => UndoEntry::Displays(
+                                    self.variables
+                                        .iter_mut()
+                                        .filter_map(|(name, variable)| {
+                                            Some((name.clone(), variable.display.take()?))
+                                        })
+                                        .collect(),
+                                ),
+                            };
+                            if self.undo_stack.len() >= MAX_UNDO {
+                                self.undo_stack.remove(0);
+                            }
+                            self.undo_stack.push(entry);
+                            self.pending_clear = None;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.pending_clear = None;
+                        }
+                    });
+                });
+        }
+
+        if !ctx.wants_keyboard_input() {
+            undo |= ctx.input_mut(|i| i.consume_shortcut(&undo_shortcut));
+        }
+        if undo && let Some(entry) = self.undo_stack.pop() {
+            match entry {
+                UndoEntry::Parameters(parameters) => {
+                    self.parameters = parameters;
+                    pending_evaluation |= true;
+                }
+                UndoEntry::Displays(displays) => {
+                    for (name, display) in displays {
+                        if let Some(variable) = self.variables.get_mut(&name) {
+                            variable.display = Some(display);
+                        }
+                    }
+                }
+            }
+        }
+
+        // F8 is already used for cycling through errors
+        if !ctx.wants_keyboard_input() {
+            ctx.input(|i| {
+                self.info_window_open ^= i.key_pressed(egui::Key::F1);
+                self.camera_window_open ^= i.key_pressed(egui::Key::F2);
+                self.parameters_window_open ^= i.key_pressed(egui::Key::F3);
+                self.code_window_open ^= i.key_pressed(egui::Key::F4);
+                self.variables_window_open ^= i.key_pressed(egui::Key::F5);
+                self.drivers_window_open ^= i.key_pressed(egui::Key::F6);
+                self.animation_window_open ^= i.key_pressed(egui::Key::F7);
+                self.changes_window_open ^= i.key_pressed(egui::Key::F9);
+                self.product_table_window_open ^= i.key_pressed(egui::Key::F10);
+            });
+        }
+
+        {
+            let mut reset_everything = false;
+            let mut copy_scene = false;
+            let mut load_scene = false;
+            egui::Window::new("Info")
+                .open(&mut self.info_window_open)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!("FPS: {:.3}", 1.0 / dt));
+                    ui.label(format!("Frame Time: {:.3}ms", 1000.0 * dt));
+                    ui.horizontal(|ui| {
+                        ui.label("Auto Save Interval:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.auto_save_interval)
+                                .speed(1.0)
+                                .range(0.0..=f32::INFINITY)
+                                .suffix("s"),
+                        );
+                    });
+                    if let Some(last_save_duration) = self.last_save_duration {
+                        ui.label(format!(
+                            "Last Save Time: {:.3}ms",
+                            last_save_duration.as_secs_f64() * 1000.0
+                        ));
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Theme:");
+                        let old_theme = self.theme;
+                        egui::ComboBox::from_id_salt("theme")
+                            .selected_text(self.theme.display_name())
+                            .show_ui(ui, |ui| {
+                                for theme in [Theme::Dark, Theme::Light] {
+                                    ui.selectable_value(
+                                        &mut self.theme,
+                                        theme,
+                                        theme.display_name(),
+                                    );
+                                }
+                            });
+                        // only replace the default color if it has not been customised
+                        if self.theme != old_theme
+                            && self.display_defaults.color == old_theme.default_object_color()
+                        {
+                            self.display_defaults.color = self.theme.default_object_color();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Angle Unit:");
+                        pending_evaluation |= egui::ComboBox::from_id_salt("angle_unit")
+                            .selected_text(self.angle_unit.display_name())
+                            .show_ui(ui, |ui| {
+                                let mut changed = false;
+                                for angle_unit in [AngleUnit::Radians, AngleUnit::Degrees] {
+                                    changed |= ui
+                                        .selectable_value(
+                                            &mut self.angle_unit,
+                                            angle_unit,
+                                            angle_unit.display_name(),
+                                        )
+                                        .changed();
+                                }
+                                changed
+                            })
+                            .inner
+                            .unwrap_or(false);
+                    });
+                    pending_evaluation |= ui
+                        .checkbox(&mut self.script_angle_unit, "Use Angle Unit In Scripts")
+                        .on_hover_text(
+                            "Makes sin/cos take and asin/acos return angles in the chosen unit, \
+                             which changes the results of existing scripts",
+                        )
+                        .changed();
+                    ui.checkbox(&mut self.scientific_notation, "Scientific Notation")
+                        .on_hover_text(
+                            "Shows very large or very small values in scientific notation",
+                        );
+                    ui.collapsing("Scene", |ui| {
+                        copy_scene |= ui.button("Copy Scene").clicked();
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(&mut self.scene_text);
+                            load_scene |= ui.button("Load Scene").clicked();
+                        });
+                        if let Some(error) = &self.scene_error {
+                            ui.label(egui::RichText::new(error).color(egui::Color32::RED));
+                        }
+                    });
+                    reset_everything |= ui.button("RESET EVERYTHING").clicked();
+                });
+            if copy_scene {
+                ctx.copy_text(ron::to_string(self).unwrap());
+            }
+            if load_scene {
+                match ron::from_str::<Self>(&self.scene_text) {
+                    Ok(scene) => {
+                        *self = Self {
+                            gpu_error: self.gpu_error.take(),
+                            ..scene
+                        };
+                        self.saved_snapshot = Some(SavedSnapshot::new(self));
+                        return;
+                    }
+                    Err(error) => self.scene_error = Some(format!("{error}")),
+                }
+            }
+            if reset_everything {
+                *self = Self {
+                    gpu_error: self.gpu_error.take(),
+                    saved_snapshot: self.saved_snapshot.take(),
+                    ..Self::default()
+                };
+                GA_FLAVOUR.store(0, Ordering::Relaxed);
+                FLIPPED_ORIENTATION.store(false, Ordering::Relaxed);
+                return;
+            }
+        }
+
+        let mut export_svg = false;
+        egui::Window::new("Camera")
+            .open(&mut self.camera_window_open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.collapsing("Transform", |ui| {
+                    edit_multivector(
+                        ui,
+                        &mut self.camera.transform,
+                        true,
+                        true,
+                        true,
+                        true,
+                        self.scientific_notation,
+                    );
+                });
+                scale_readout(ui, self.camera.transform, self.scientific_notation);
+                if ui.button("Normalize Transform").clicked() {
+                    self.camera.transform = self.camera.transform.normalized();
+                }
+                export_svg |= ui
+                    .button("Copy View As SVG")
+                    .on_hover_text("Copies the displayed points, lines, and polygons as an svg image")
+                    .clicked();
+                ui.collapsing("Recenter", |ui| {
+                    let points = self
+                        .variables
+                        .iter()
+                        .filter(|(_, variable)| variable.value.grades(0.0)[2])
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>();
+                    select_variable(ui, "recenter_point", "Point:", &mut self.recenter_point, &points);
+                    let coordinates = self
+                        .recenter_point
+                        .as_ref()
+                        .and_then(|name| self.variables.get(name))
+                        .map(|variable| variable.value.point_coordinates());
+                    if let Some(None) = coordinates {
+                        ui.label(
+                            egui::RichText::new("Cannot recenter on an ideal point")
+                                .color(egui::Color32::YELLOW),
+                        );
+                    }
+                    if ui
+                        .add_enabled(
+                            matches!(coordinates, Some(Some(_))),
+                            egui::Button::new("Recenter Camera"),
+                        )
+                        .clicked()
+                        && let Some(Some((x, y))) = coordinates
+                    {
+                        self.camera.transform = recentered_transform(&self.camera, x, y);
+                    }
+                });
+                ui.collapsing("Follow Variable", |ui| {
+                    let motors = self
+                        .variables
+                        .iter()
+                        .filter(|(_, variable)| driver_motor(variable.value).is_some())
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>();
+                    ui.horizontal(|ui| {
+                        select_variable(ui, "camera_driver", "Motor:", &mut self.camera.driver, &motors);
+                        if ui.button("Stop").clicked() {
+                            self.camera.driver = None;
+                        }
+                    });
+                    if let Some(variable) = self
+                        .camera
+                        .driver
+                        .as_ref()
+                        .and_then(|name| self.variables.get(name))
+                        && driver_motor(variable.value).is_none()
+                    {
+                        ui.label(
+                            egui::RichText::new("Not a motor, the camera is not following it")
+                                .color(egui::Color32::YELLOW),
+                        );
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Smoothing:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.camera.driver_smoothing)
+                                .speed(0.01)
+                                .range(0.0..=f32::INFINITY)
+                                .suffix(" s"),
+                        )
+                        .on_hover_text("0 follows the motor exactly, larger values ease towards it");
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("View Height:");
+                    ui.add(egui::DragValue::new(&mut self.camera.view_height).speed(0.1));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Max View Height:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.camera.max_view_height)
+                            .speed(1.0)
+                            .range(0.1..=f32::INFINITY),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Move Speed:");
+                    ui.add(egui::DragValue::new(&mut self.camera.move_speed).speed(0.1));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Zoom Speed:");
+                    ui.add(egui::DragValue::new(&mut self.camera.zoom_speed).speed(0.1));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Line Thickness:");
+                    ui.add(egui::DragValue::new(&mut self.camera.line_thickness).speed(0.01));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Point Radius:");
+                    ui.add(egui::DragValue::new(&mut self.camera.point_radius).speed(0.01));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Line Extent:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.camera.line_extent)
+                            .speed(0.1)
+                            .range(0.0..=f32::INFINITY),
+                    )
+                    .on_hover_text("How far lines extend from their closest point to the origin, 0 for infinite lines");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Outlines:");
+                    ui.checkbox(&mut self.camera.outlines, "");
+                    ui.add_enabled(
+                        self.camera.outlines,
+                        egui::DragValue::new(&mut self.camera.outline_width)
+                            .speed(0.1)
+                            .range(0.0..=f32::INFINITY)
+                            .suffix("px"),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Layer Badges:");
+                    ui.checkbox(&mut self.camera.layer_badges, "")
+                        .on_hover_text("Shows the layer of each displayed object next to it");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Line Orientation:");
+                    ui.checkbox(&mut self.camera.line_orientation, "").on_hover_text(
+                        "Darkens the side of each line that its normal points away from, \
+                         so negating a line flips which side is dark",
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Snap To Points:");
+                    ui.checkbox(&mut self.camera.snap_to_points, "").on_hover_text(format!(
+                        "Dragging a point within {POINT_SNAP_DISTANCE} pixels of a displayed point \
+                         moves it exactly onto it, hold alt to drag freely"
+                    ));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Coincident Badges:");
+                    ui.checkbox(&mut self.camera.coincident_badges, "").on_hover_text(
+                        "Marks displayed objects that are in the same place as others, \
+                         with how many there are and their names",
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Show Weight:");
+                    ui.checkbox(&mut self.camera.show_weight, "").on_hover_text(
+                        "Scales each displayed object by its magnitude, \
+                         so a point with weight 2 is drawn twice as big",
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Ideal Line:");
+                    ui.checkbox(&mut self.camera.ideal_line, "").on_hover_text(
+                        "Draws the line at infinity as a border around the view \
+                         and marks the directions of displayed ideal points on it, \
+                         only in the euclidean flavour",
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Gizmo:");
+                    ui.checkbox(&mut self.camera.gizmo, "").on_hover_text(
+                        "Shows handles in the middle of the view, \
+                         drag the arrows to move the camera and the circle to rotate it",
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Spin:");
+                    ui.checkbox(&mut self.camera.spin, "")
+                        .on_hover_text("Keeps rotating the camera around the middle of the view");
+                    let mut spin_speed = self.angle_unit.from_radians(self.camera.spin_speed);
+                    if ui
+                        .add_enabled(
+                            self.camera.spin,
+                            egui::DragValue::new(&mut spin_speed).suffix(match self.angle_unit {
+                                AngleUnit::Radians => " rad/s",
+                                AngleUnit::Degrees => "°/s",
+                            }),
+                        )
+                        .changed()
+                    {
+                        self.camera.spin_speed = self.angle_unit.to_radians(spin_speed);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Snap Angle:");
+                    let mut snap_angle = self.angle_unit.from_radians(self.camera.snap_angle);
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut snap_angle)
+                                .range(0.0..=f32::INFINITY)
+                                .suffix(match self.angle_unit {
+                                    AngleUnit::Radians => " rad",
+                                    AngleUnit::Degrees => "°",
+                                }),
+                        )
+                        .on_hover_text(
+                            "Rotating with the gizmo while holding shift snaps to multiples of this, \
+                             0 to turn snapping off",
+                        )
+                        .changed()
+                    {
+                        self.camera.snap_angle = self.angle_unit.to_radians(snap_angle);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Flavour:");
+                    let mut flavour = GA_FLAVOUR.load(Ordering::Relaxed);
+                    let name = |flavour: u8| match flavour {
+                        0 => "Euclidean",
+                        1 => "Hyperbolic",
+                        2 => "Spherical",
+                        _ => unreachable!(),
+                    };
+                    if egui::ComboBox::from_id_salt("flavour")
+                        .selected_text(name(flavour))
+                        .show_ui(ui, |ui| {
+                            let mut changed = false;
+                            changed |= ui.selectable_value(&mut flavour, 0, name(0)).changed();
+                            changed |= ui.selectable_value(&mut flavour, 1, name(1)).changed();
+                            changed |= ui.selectable_value(&mut flavour, 2, name(2)).changed();
+                            changed
+                        })
+                        .inner
+                        .unwrap_or(false)
+                    {
+                        GA_FLAVOUR.store(flavour, Ordering::Relaxed);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Flip Orientation:");
+                    let mut flipped_orientation = FLIPPED_ORIENTATION.load(Ordering::Relaxed);
+                    if ui
+                        .checkbox(&mut flipped_orientation, "")
+                        .on_hover_text(
+                            "Negates the dual (and so the regressive product), \
+                             which changes the results of existing scripts",
+                        )
+                        .changed()
+                    {
+                        FLIPPED_ORIENTATION.store(flipped_orientation, Ordering::Relaxed);
+                        pending_evaluation |= true;
+                    }
+                });
+            });
+
+        egui::Window::new("Parameters")
+            .open(&mut self.parameters_window_open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("New Parameter").clicked() {
+                        self.parameters.push(Parameter {
+                            name: "unnamed".into(),
+                            type_: ParameterType::Grade0,
+                            value: Multivector::ZERO,
+                            keep_normalized: false,
+                            polar: false,
+                        });
+                        pending_evaluation |= true;
+                    }
+                    if ui
+                        .add_enabled(
+                            !self.parameters.is_empty(),
+                            egui::Button::new(ClearAction::Parameters.display_name()),
+                        )
+                        .clicked()
+                    {
+                        self.pending_clear = Some(ClearAction::Parameters);
+                    }
+                });
+                ui.collapsing("Import Points", |ui| {
+                    ui.label("One x,y pair per line:");
+                    ui.text_edit_multiline(&mut self.import_text);
+                    if ui.button("Import Points").clicked() {
+                        let (points, errors) = parse_points_csv(&self.import_text);
+                        self.import_errors = errors;
+                        let mut index = 0usize;
+                        for (x, y) in points {
+                            let name = loop {
+                                let name = format!("p{index}");
+                                index += 1;
+                                if !self.variables.contains_key(&name)
+                                    && !self
+                                        .parameters
+                                        .iter()
+                                        .any(|parameter| parameter.name == name)
+                                {
+                                    break name;
+                                }
+                            };
+                            self.variables.insert(
+                                name.clone(),
+                                Variable {
+                                    value: Multivector::ZERO,
+                                    frozen: None,
+                                    group: None,
+                                    display: Some(self.display_defaults.display()),
+                                },
+                            );
+                            self.parameters.push(Parameter {
+                                name,
+                                type_: ParameterType::Grade2,
+                                value: Multivector::point(x, y),
+                                keep_normalized: false,
+                                polar: false,
+                            });
+                            pending_evaluation |= true;
+                        }
+                    }
+                    for error in &self.import_errors {
+                        ui.label(egui::RichText::new(error).color(egui::Color32::RED));
+                    }
+                });
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let mut i = 0usize;
+                    let mut delete = false;
+                    self.parameters.retain_mut(|parameter| {
+                        let header = egui::CollapsingHeader::new(&parameter.name)
+                            .id_salt(i)
+                            .default_open(self.expanded_parameters.contains(&parameter.name))
+                            .show(ui, |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label("Name:");
+                                    pending_evaluation |= edit_name(ui, &mut parameter.name);
+                                });
+
+                                ui.horizontal(|ui| {
+                                    ui.label("Type:");
+                                    if egui::ComboBox::from_id_salt("type")
+                                        .selected_text(parameter.type_.display_name())
+                                        .show_ui(ui, |ui| {
+                                            let mut changed = false;
+                                            for type_ in [
+                                                ParameterType::Grade0,
+                                                ParameterType::Grade1,
+                                                ParameterType::Grade2,
+                                                ParameterType::Grade3,
+                                                ParameterType::Multivector,
+                                            ] {
+                                                changed |= ui
+                                                    .selectable_value(
+                                                        &mut parameter.type_,
+                                                        type_,
+                                                        type_.display_name(),
+                                                    )
+                                                    .changed();
+                                            }
+                                            changed
+                                        })
+                                        .inner
+                                        .unwrap_or(false)
+                                    {
+                                        parameter.value = match parameter.type_ {
+                                            ParameterType::Grade0 => parameter.value.grade0(),
+                                            ParameterType::Grade1 => parameter.value.grade1(),
+                                            ParameterType::Grade2 => parameter.value.grade2(),
+                                            ParameterType::Grade3 => parameter.value.grade3(),
+                                            ParameterType::Multivector => parameter.value,
+                                        };
+                                        pending_evaluation |= true;
+                                    }
+                                });
+
+                                ui.horizontal(|ui| {
+                                    if ui.button("Normalise").clicked() {
+                                        parameter.value = parameter.value.normalized();
+                                        pending_evaluation |= true;
+                                    }
+                                    if ui
+                                        .checkbox(&mut parameter.keep_normalized, "Keep Normalised")
+                                        .on_hover_text("Normalises the value after every edit")
+                                        .changed()
+                                        && parameter.keep_normalized
+                                    {
+                                        parameter.value = parameter.value.normalized();
+                                        pending_evaluation |= true;
+                                    }
+                                    if ui
+                                        .button("Reset Value")
+                                        .on_hover_text(
+                                            "Resets to the basis blade matching the name, or zero",
+                                        )
+                                        .clicked()
+                                    {
+                                        parameter.value = Multivector::basis_blade(&parameter.name)
+                                            .unwrap_or(Multivector::ZERO);
+                                        pending_evaluation |= true;
+                                    }
+                                });
+
+                                let (grade0, grade1, grade2, grade3) = match parameter.type_ {
+                                    ParameterType::Grade0 => (true, false, false, false),
+                                    ParameterType::Grade1 => (false, true, false, false),
+                                    ParameterType::Grade2 => (false, false, true, false),
+                                    ParameterType::Grade3 => (false, false, false, true),
+                                    ParameterType::Multivector => (true, true, true, true),
+                                };
+
+                                let can_be_polar = parameter.type_ == ParameterType::Grade1;
+                                if can_be_polar {
+                                    ui.checkbox(&mut parameter.polar, "Polar").on_hover_text(
+                                        "Edits e1 and e2 as a magnitude and an angle counterclockwise from e1",
+                                    );
+                                }
+                                let changed = if can_be_polar && parameter.polar {
+                                    edit_polar(
+                                        ui,
+                                        &mut parameter.value,
+                                        self.angle_unit,
+                                        self.scientific_notation,
+                                    )
+                                } else {
+                                    edit_multivector(
+                                        ui,
+                                        &mut parameter.value,
+                                        grade0,
+                                        grade1,
+                                        grade2,
+                                        grade3,
+                                        self.scientific_notation,
+                                    )
+                                };
+                                if changed {
+                                    if parameter.keep_normalized {
+                                        parameter.value = parameter.value.normalized();
+                                    }
+                                    pending_evaluation |= true;
+                                }
+                                if let [true, false, true, false] = parameter.value.grades(0.0) {
+                                    scale_readout(ui, parameter.value, self.scientific_notation);
+                                }
+
+                                delete = ui.button("Delete").clicked();
+                                pending_evaluation |= delete;
+                            });
+                        record_expanded(
+                            &mut self.expanded_parameters,
+                            &parameter.name,
+                            !header.fully_closed(),
+                        );
+
+                        i += 1;
+                        !delete
+                    });
+                    ui.allocate_space(ui.available_size());
+                });
+            });
+
+        let mut sample_statistics = false;
+        egui::Window::new("Drivers")
+            .open(&mut self.drivers_window_open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                if ui.button("New Driver").clicked() {
+                    self.drivers.push(Driver {
+                        name: "unnamed".into(),
+                        value: 0.0,
+                        min: 0.0,
+                        max: 1.0,
+                        step: 0.0,
+                    });
+                    pending_evaluation |= true;
+                }
+                ui.collapsing("Statistics", |ui| {
+                    let drivers = self
+                        .drivers
+                        .iter()
+                        .map(|driver| &driver.name)
+                        .collect::<Vec<_>>();
+                    select_variable(
+                        ui,
+                        "statistics_driver",
+                        "Driver:",
+                        &mut self.statistics_driver,
+                        &drivers,
+                    );
+                    let scalars = self
+                        .variables
+                        .iter()
+                        .filter(|(_, variable)| variable.value.is_scalar())
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>();
+                    select_variable(
+                        ui,
+                        "statistics_variable",
+                        "Variable:",
+                        &mut self.statistics_variable,
+                        &scalars,
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label("Samples:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.statistics_samples)
+                                .range(1..=MAX_SWEEP_SAMPLES),
+                        );
+                    });
+                    sample_statistics = ui
+                        .add_enabled(
+                            self.statistics_driver.is_some() && self.statistics_variable.is_some(),
+                            egui::Button::new("Sample"),
+                        )
+                        .on_hover_text(
+                            "Runs the code for each value of the driver across its range \
+                             and summarises the variable",
+                        )
+                        .clicked();
+
+                    if let Some(statistics) = &self.statistics {
+                        ui.label(format!(
+                            "Min: {}",
+                            format_readout(statistics.min, self.scientific_notation)
+                        ));
+                        ui.label(format!(
+                            "Max: {}",
+                            format_readout(statistics.max, self.scientific_notation)
+                        ));
+                        ui.label(format!(
+                            "Mean: {}",
+                            format_readout(statistics.mean, self.scientific_notation)
+                        ));
+                        if statistics.skipped > 0 {
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "{} samples failed to evaluate",
+                                    statistics.skipped
+                                ))
+                                .color(egui::Color32::YELLOW),
+                            );
+                        }
+
+                        let (rect, _) = ui.allocate_exact_size(
+                            egui::vec2(ui.available_width().max(100.0), 60.0),
+                            egui::Sense::hover(),
+                        );
+                        ui.painter()
+                            .rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+                        let tallest = statistics.histogram.iter().copied().max().unwrap_or(1);
+                        let bar_width = rect.width() / statistics.histogram.len() as f32;
+                        for (i, &count) in statistics.histogram.iter().enumerate() {
+                            let height = rect.height() * count as f32 / tallest as f32;
+                            let left = rect.left() + bar_width * i as f32;
+                            ui.painter().rect_filled(
+                                egui::Rect::from_min_max(
+                                    egui::pos2(left + 1.0, rect.bottom() - height),
+                                    egui::pos2(left + bar_width - 1.0, rect.bottom()),
+                                ),
+                                0.0,
+                                ui.visuals().selection.bg_fill,
+                            );
+                        }
+                    }
+                });
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let mut i = 0usize;
+                    self.drivers.retain_mut(|driver| {
+                        let mut delete = false;
+                        egui::CollapsingHeader::new(&driver.name)
+                            .id_salt(i)
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label("Name:");
+                                    pending_evaluation |= edit_name(ui, &mut driver.name);
+                                });
+
+                                ui.horizontal(|ui| {
+                                    ui.label("Min:");
+                                    ui.add(egui::DragValue::new(&mut driver.min).speed(0.1));
+                                    ui.label("Max:");
+                                    ui.add(egui::DragValue::new(&mut driver.max).speed(0.1));
+                                    ui.label("Step:");
+                                    ui.add(
+                                        egui::DragValue::new(&mut driver.step)
+                                            .speed(0.01)
+                                            .range(0.0..=f32::INFINITY),
+                                    );
+                                });
+
+                                let mut slider =
+                                    egui::Slider::new(&mut driver.value, driver.min..=driver.max);
+                                if driver.step > 0.0 {
+                                    slider = slider.step_by(driver.step as f64);
+                                }
+                                pending_evaluation |= ui.add(slider).changed();
+
+                                delete = ui.button("Delete").clicked();
+                                pending_evaluation |= delete;
+                            });
+
+                        i += 1;
+                        !delete
+                    });
+                    ui.allocate_space(ui.available_size());
+                });
+            });
+
+        if sample_statistics
+            && let (Some(driver), Some(variable)) =
+                (&self.statistics_driver, &self.statistics_variable)
+        {
+            let samples = self.sample_sweep(
+                driver,
+                variable,
+                self.statistics_samples.min(MAX_SWEEP_SAMPLES),
+            );
+            self.statistics = SweepStatistics::new(&samples);
+        }
+
+        let mut bake_frames = false;
+        egui::Window::new("Animation")
+            .open(&mut self.animation_window_open)
+            .resizable(false)
+            .show(ctx, |ui| {
                 ui.horizontal(|ui| {
-                    ui.label("Move Speed:");
-                    ui.add(egui::DragValue::new(&mut self.camera.move_speed).speed(0.1));
+                    let play_text = if self.animation.playing {
+                        "Pause"
+                    } else {
+                        "Play"
+                    };
+                    if ui.button(play_text).clicked() {
+                        self.animation.playing = !self.animation.playing;
+                    }
+                    if ui.button("Reverse").clicked() {
+                        self.animation.speed = -self.animation.speed;
+                    }
+                    if ui.button("Reset").clicked() {
+                        self.animation.time = 0.0;
+                        pending_evaluation |= true;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Time:");
+                    pending_evaluation |= ui
+                        .add(egui::DragValue::new(&mut self.animation.time).speed(0.01))
+                        .changed();
+                    ui.label(format!("(t = {:.3})", self.animation.phase()));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Speed:");
+                    ui.add(egui::DragValue::new(&mut self.animation.speed).speed(0.01));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Duration:");
+                    pending_evaluation |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.animation.duration)
+                                .speed(0.1)
+                                .range(0.0..=f32::INFINITY),
+                        )
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Mode:");
+                    pending_evaluation |= egui::ComboBox::from_id_salt("playback_mode")
+                        .selected_text(self.animation.mode.display_name())
+                        .show_ui(ui, |ui| {
+                            let mut changed = false;
+                            for mode in [
+                                PlaybackMode::Loop,
+                                PlaybackMode::PingPong,
+                                PlaybackMode::Once,
+                            ] {
+                                changed |= ui
+                                    .selectable_value(
+                                        &mut self.animation.mode,
+                                        mode,
+                                        mode.display_name(),
+                                    )
+                                    .changed();
+                            }
+                            changed
+                        })
+                        .inner
+                        .unwrap_or(false);
+                });
+                ui.collapsing("Bake Frames", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Folder:");
+                        ui.text_edit_singleline(&mut self.bake.folder);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Frames:");
+                        ui.add(egui::DragValue::new(&mut self.bake.frames).range(1..=MAX_BAKE_FRAMES));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Duration:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.bake.duration)
+                                .speed(0.1)
+                                .range(0.0..=f32::INFINITY)
+                                .suffix(" s"),
+                        );
+                    });
+                    bake_frames = ui
+                        .button("Bake")
+                        .on_hover_text(
+                            "Writes the view at evenly spaced animation times as numbered svg files, \
+                             the same as Copy View As SVG",
+                        )
+                        .clicked();
+                    match &self.bake_status {
+                        Some(Ok(status)) => {
+                            ui.label(status);
+                        }
+                        Some(Err(error)) => {
+                            ui.label(egui::RichText::new(error).color(egui::Color32::RED));
+                        }
+                        None => {}
+                    }
+                });
+            });
+
+        if self.animation.playing {
+            self.animation.advance(dt);
+            pending_evaluation |= true;
+        }
+
+        let mut jump_to_error = false;
+        ctx.input(|i| {
+            if i.key_pressed(egui::Key::F8) {
+                self.selected_error =
+                    cycle_index(self.selected_error, self.errors.len(), i.modifiers.shift);
+                jump_to_error = self.selected_error.is_some();
+            }
+        });
+
+        let mut inserted_product = None;
+        egui::Window::new("Product Table")
+            .open(&mut self.product_table_window_open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::ComboBox::from_label("Product")
+                    .selected_text(self.product_table.display_name())
+                    .show_ui(ui, |ui| {
+                        for product in [
+                            TableProduct::Geometric,
+                            TableProduct::Wedge,
+                            TableProduct::Inner,
+                        ] {
+                            ui.selectable_value(
+                                &mut self.product_table,
+                                product,
+                                product.display_name(),
+                            );
+                        }
+                    });
+
+                // the blades come from the components so the table is computed by the real products
+                let blades = Multivector::ZERO.components().map(|(name, _)| match name {
+                    "s" => (
+                        "1",
+                        Multivector {
+                            s: 1.0,
+                            ..Multivector::ZERO
+                        },
+                    ),
+                    name => (name, Multivector::basis_blade(name).unwrap()),
+                });
+                egui::Grid::new("product_table")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label(self.product_table.operator());
+                        for (name, _) in blades {
+                            ui.strong(name);
+                        }
+                        ui.end_row();
+
+                        for (left_name, left) in blades {
+                            ui.strong(left_name);
+                            for (right_name, right) in blades {
+                                let product = self.product_table.apply(left, right);
+                                if ui
+                                    .add(
+                                        egui::Label::new(
+                                            egui::RichText::new(product.to_string()).monospace(),
+                                        )
+                                        .sense(egui::Sense::click()),
+                                    )
+                                    .on_hover_text("Click to add this product to the code")
+                                    .clicked()
+                                {
+                                    inserted_product = Some(format!(
+                                        "{left_name} {} {right_name}",
+                                        self.product_table.operator()
+                                    ));
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if let Some(expression) = inserted_product {
+            let name = self.unique_variable_name("product");
+            if !self.code.is_empty() && !self.code.ends_with('\n') {
+                self.code.push('\n');
+            }
+            self.code.push_str(&format!("{name} = {expression};\n"));
+            pending_evaluation |= true;
+        }
+
+        let mut evaluated_selection = None;
+        let script_settings = self.script_settings();
+        egui::Window::new("Code")
+            .open(&mut self.code_window_open)
+            .scroll(true)
+            .show(ctx, |ui| {
+                if !self.errors.is_empty() {
+                    // a new header state is used whenever the list crosses the threshold,
+                    // so it collapses when it gets long but stays however the user left it after that
+                    let start_open = errors_start_open(self.errors.len(), self.collapse_long_errors);
+                    egui::CollapsingHeader::new(
+                        egui::RichText::new(format!("Errors ({})", self.errors.len()))
+                            .heading()
+                            .color(egui::Color32::RED),
+                    )
+                    .id_salt(("errors", start_open))
+                    .default_open(start_open)
+                    .show(ui, |ui| {
+                        for (i, error) in self.errors.iter().enumerate() {
+                            let mut text =
+                                egui::RichText::new(error.to_string()).color(egui::Color32::RED);
+                            if self.selected_error == Some(i) {
+                                text =
+                                    text.strong().background_color(egui::Color32::from_gray(60));
+                            }
+                            if ui
+                                .add(egui::Label::new(text).sense(egui::Sense::click()))
+                                .clicked()
+                            {
+                                self.selected_error = Some(i);
+                                jump_to_error = true;
+                            }
+                        }
+                    });
+                }
+
+                if !self.warnings.is_empty() {
+                    ui.heading("Warnings:");
+                    for warning in &self.warnings {
+                        ui.label(
+                            egui::RichText::new(warning.to_string()).color(egui::Color32::YELLOW),
+                        );
+                    }
+                }
+
+                ui.checkbox(&mut self.collapse_long_errors, "Collapse Long Error Lists")
+                    .on_hover_text(format!(
+                        "Starts the error list collapsed when there are more than {ERRORS_COLLAPSE_THRESHOLD} errors, \
+                         so the editor does not jump around"
+                    ));
+                pending_evaluation |= ui
+                    .checkbox(
+                        &mut self.warn_unused_variables,
+                        "Warn About Unused Variables",
+                    )
+                    .on_hover_text(
+                        "Warns about variables that are not displayed, pinned, or read by the code",
+                    )
+                    .changed();
+                ui.horizontal(|ui| {
+                    ui.label("Evaluation Budget:");
+                    pending_evaluation |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.evaluation_budget)
+                                .range(1..=usize::MAX)
+                                .suffix(" steps"),
+                        )
+                        .on_hover_text(
+                            "How many expressions can be evaluated before the code is stopped, \
+                             every expression runs once so this limits how long the code can be",
+                        )
+                        .changed();
+                });
+                pending_evaluation |= ui
+                    .checkbox(
+                        &mut self.strict_scalar_arguments,
+                        "Strict Scalar Arguments",
+                    )
+                    .on_hover_text(
+                        "Makes passing a non-scalar to a builtin like sin an error instead of a warning",
+                    )
+                    .changed();
+                pending_evaluation |= ui
+                    .checkbox(&mut self.parallel_evaluation, "Parallel Evaluation")
+                    .on_hover_text(
+                        "Evaluates statements that do not depend on each other on several threads, \
+                         falling back to running in order when a name is assigned more than once \
+                         or the code could run out of budget",
+                    )
+                    .changed();
+
+                if !self.selection_output.is_empty() {
+                    ui.horizontal(|ui| {
+                        ui.heading("Output:");
+                        if ui.button("Clear").clicked() {
+                            self.selection_output.clear();
+                        }
+                    });
+                    for line in &self.selection_output {
+                        ui.monospace(line);
+                    }
+                }
+
+                let code_id = egui::Id::new("code");
+                let evaluate_shortcut =
+                    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Enter);
+                let comment_shortcut =
+                    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Slash);
+                let (evaluate_clicked, comment_clicked) = ui
+                    .horizontal(|ui| {
+                        let evaluate_clicked = ui
+                            .button("Evaluate Selection")
+                            .on_hover_text(format!(
+                                "Runs only the selected statements without changing any variables ({})",
+                                ui.ctx().format_shortcut(&evaluate_shortcut)
+                            ))
+                            .clicked();
+                        let comment_clicked = ui
+                            .button("Toggle Comment")
+                            .on_hover_text(format!(
+                                "Comments out the selected lines, or uncomments them if they all are ({})",
+                                ui.ctx().format_shortcut(&comment_shortcut)
+                            ))
+                            .clicked();
+                        (evaluate_clicked, comment_clicked)
+                    })
+                    .inner;
+                let code_focused = ui.memory(|memory| memory.has_focus(code_id));
+                let evaluate_pressed =
+                    code_focused && ui.input_mut(|i| i.consume_shortcut(&evaluate_shortcut));
+                let comment_pressed =
+                    code_focused && ui.input_mut(|i| i.consume_shortcut(&comment_shortcut));
+                let selection = egui::TextEdit::load_state(ctx, code_id)
+                    .and_then(|state| state.cursor.char_range())
+                    .map(|range| {
+                        let [start, end] = range.sorted();
+                        let byte_index = |index: usize| {
+                            self.code
+                                .char_indices()
+                                .nth(index)
+                                .map_or(self.code.len(), |(i, _)| i)
+                        };
+                        byte_index(start.index)..byte_index(end.index)
+                    });
+                if (evaluate_clicked || evaluate_pressed)
+                    && let Some(selection) = selection.clone()
+                {
+                    evaluated_selection = Some(selection);
+                }
+                if (comment_clicked || comment_pressed)
+                    && let Some(selection) = selection
+                {
+                    self.code = toggle_line_comments(&self.code, selection);
+                    pending_evaluation |= true;
+                }
+                if jump_to_error
+                    && let Some(error) = self.selected_error.and_then(|i| self.errors.get(i))
+                {
+                    let position = error.location.position.min(self.code.len());
+                    let start = self.code[..position].chars().count();
+                    let end = match error.end {
+                        Some(end) => self.code[..end.position.min(self.code.len())]
+                            .chars()
+                            .count()
+                            .max(start),
+                        None => {
+                            start
+                                + self.code[position..]
+                                    .lines()
+                                    .next()
+                                    .map_or(0, |line| line.chars().count())
+                        }
+                    };
+                    let mut state = egui::TextEdit::load_state(ctx, code_id).unwrap_or_default();
+                    state
+                        .cursor
+                        .set_char_range(Some(egui::text::CCursorRange::two(
+                            egui::text::CCursor::new(start),
+                            egui::text::CCursor::new(end),
+                        )));
+                    state.store(ctx, code_id);
+                    ctx.memory_mut(|memory| memory.request_focus(code_id));
+                }
+
+                // leave a row below the editor for the preview of the statement under the cursor
+                let preview_height = ui.text_style_height(&egui::TextStyle::Monospace)
+                    + ui.spacing().item_spacing.y;
+                let output = egui::TextEdit::multiline(&mut self.code)
+                    .id(code_id)
+                    .code_editor()
+                    .desired_width(f32::INFINITY)
+                    .min_size(ui.available_size() - egui::vec2(0.0, preview_height))
+                    .show(ui);
+                pending_evaluation |= output.response.changed();
+
+                let preview = output.cursor_range.and_then(|cursor_range| {
+                    let cursor = self
+                        .code
+                        .char_indices()
+                        .nth(cursor_range.primary.ccursor.index)
+                        .map_or(self.code.len(), |(i, _)| i);
+                    preview_statement(
+                        &self.code,
+                        cursor,
+                        &self.variables,
+                        script_settings,
+                    )
+                });
+                match preview {
+                    Some(Ok(result)) => {
+                        ui.monospace(result);
+                    }
+                    Some(Err(error)) => {
+                        ui.label(
+                            egui::RichText::new(error)
+                                .monospace()
+                                .color(egui::Color32::RED),
+                        );
+                    }
+                    None => {
+                        ui.monospace("");
+                    }
+                }
+
+                if jump_to_error && let Some(cursor_range) = output.cursor_range {
+                    let cursor_rect = output
+                        .galley
+                        .pos_from_cursor(&cursor_range.primary)
+                        .translate(output.galley_pos.to_vec2());
+                    ui.scroll_to_rect(cursor_rect, Some(egui::Align::Center));
+                }
+            });
+
+        if let Some(selection) = evaluated_selection {
+            self.selection_output = if selection.is_empty() {
+                vec!["Nothing is selected".into()]
+            } else {
+                self.evaluate_selection(selection)
+            };
+        }
+
+        let mut show_intersection = false;
+        let mut transform_all = None;
+        egui::Window::new("Variables")
+            .open(&mut self.variables_window_open)
+            .scroll([false, true])
+            .show(ctx, |ui| {
+                if ui
+                    .add_enabled(
+                        self.variables.values().any(|variable| variable.display.is_some()),
+                        egui::Button::new(ClearAction::Displays.display_name()),
+                    )
+                    .clicked()
+                {
+                    self.pending_clear = Some(ClearAction::Displays);
+                }
+                if ui
+                    .button("Copy Variables As Script")
+                    .on_hover_text(
+                        "Copies the current value of every computed variable as an assignment",
+                    )
+                    .clicked()
+                {
+                    let script = self
+                        .variables
+                        .iter()
+                        .filter(|(name, _)| {
+                            name.as_str() != TIME_VARIABLE
+                                && !self.drivers.iter().any(|driver| &driver.name == *name)
+                                && !self
+                                    .parameters
+                                    .iter()
+                                    .any(|parameter| &parameter.name == *name)
+                        })
+                        .map(|(name, variable)| {
+                            let value = if self.clean_values {
+                                variable.value.approx_zero(CLEAN_EPSILON)
+                            } else {
+                                variable.value
+                            };
+                            format!("{name} = {value};\n")
+                        })
+                        .collect::<String>();
+                    ui.ctx().copy_text(script);
+                }
+                ui.checkbox(&mut self.clean_values, "Clean Tiny Values")
+                    .on_hover_text(format!(
+                        "Shows components smaller than {CLEAN_EPSILON} as zero, \
+                         the stored values are not changed"
+                    ));
+                ui.collapsing("Display Defaults", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Color:");
+                        ui.color_edit_button_rgb(self.display_defaults.color.as_mut());
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Layer:");
+                        ui.add(egui::Slider::new(
+                            &mut self.display_defaults.layer,
+                            0.0..=1.0,
+                        ));
+                    });
+                });
+
+                ui.collapsing("Show Intersection", |ui| {
+                    let lines = self
+                        .variables
+                        .iter()
+                        .filter(|(_, variable)| variable.value.grades(0.0)[1])
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>();
+                    select_variable(
+                        ui,
+                        "intersection_first",
+                        "First:",
+                        &mut self.intersection_lines.0,
+                        &lines,
+                    );
+                    select_variable(
+                        ui,
+                        "intersection_second",
+                        "Second:",
+                        &mut self.intersection_lines.1,
+                        &lines,
+                    );
+                    let can_intersect = matches!(
+                        &self.intersection_lines,
+                        (Some(first), Some(second)) if first != second
+                    );
+                    if can_intersect
+                        && let (Some(first), Some(second)) = &self.intersection_lines
+                        && let (Some(first), Some(second)) =
+                            (self.variables.get(first), self.variables.get(second))
+                    {
+                        match first.value.wedge(second.value).point_coordinates() {
+                            Some((x, y)) => ui.label(format!(
+                                "Intersection: ({}, {})",
+                                format_readout(x, self.scientific_notation),
+                                format_readout(y, self.scientific_notation)
+                            )),
+                            None => ui.label("Parallel (no finite intersection)"),
+                        };
+                    }
+                    show_intersection |= ui
+                        .add_enabled(can_intersect, egui::Button::new("Show Intersection"))
+                        .clicked();
+                });
+
+                ui.collapsing("Join Points", |ui| {
+                    let points = self
+                        .variables
+                        .iter()
+                        .filter(|(_, variable)| variable.value.grades(0.0)[2])
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>();
+                    select_variable(ui, "join_first", "First:", &mut self.join_points.0, &points);
+                    select_variable(
+                        ui,
+                        "join_second",
+                        "Second:",
+                        &mut self.join_points.1,
+                        &points,
+                    );
+                    if let (Some(first), Some(second)) = &self.join_points
+                        && first != second
+                    {
+                        if ui.button("Add Join").clicked() {
+                            self.joins.push(Join {
+                                first: first.clone(),
+                                second: second.clone(),
+                                color: cgmath::Vector3 {
+                                    x: 1.0,
+                                    y: 1.0,
+                                    z: 0.0,
+                                },
+                            });
+                        }
+                    } else {
+                        ui.add_enabled(false, egui::Button::new("Add Join"));
+                    }
+
+                    self.joins.retain_mut(|join| {
+                        let mut delete = false;
+                        ui.horizontal(|ui| {
+                            ui.color_edit_button_rgb(join.color.as_mut());
+                            ui.label(format!("{} & {}", join.first, join.second));
+                            delete = ui.button("Delete").clicked();
+                        });
+                        !delete
+                    });
+                });
+
+                ui.collapsing("Motor Orbits", |ui| {
+                    let motors = self
+                        .variables
+                        .iter()
+                        .filter(|(_, variable)| {
+                            let [scalar, _, bivector, _] = variable.value.grades(0.0);
+                            scalar && bivector
+                        })
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>();
+                    let points = self
+                        .variables
+                        .iter()
+                        .filter(|(_, variable)| variable.value.grades(0.0)[2])
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>();
+                    select_variable(
+                        ui,
+                        "orbit_motor",
+                        "Motor:",
+                        &mut self.orbit_selection.0,
+                        &motors,
+                    );
+                    select_variable(
+                        ui,
+                        "orbit_point",
+                        "Point:",
+                        &mut self.orbit_selection.1,
+                        &points,
+                    );
+                    if let (Some(motor), Some(point)) = &self.orbit_selection {
+                        if ui.button("Add Orbit").clicked() {
+                            self.orbits.push(Orbit {
+                                motor: motor.clone(),
+                                point: point.clone(),
+                                samples: 32,
+                                color: cgmath::Vector3 {
+                                    x: 0.0,
+                                    y: 1.0,
+                                    z: 1.0,
+                                },
+                            });
+                        }
+                    } else {
+                        ui.add_enabled(false, egui::Button::new("Add Orbit"));
+                    }
+
+                    self.orbits.retain_mut(|orbit| {
+                        let mut delete = false;
+                        ui.horizontal(|ui| {
+                            ui.color_edit_button_rgb(orbit.color.as_mut());
+                            ui.label(format!("{} around {}", orbit.point, orbit.motor));
+                            ui.add(
+                                egui::DragValue::new(&mut orbit.samples)
+                                    .range(1..=1000)
+                                    .suffix(" samples"),
+                            );
+                            delete = ui.button("Delete").clicked();
+                        });
+                        !delete
+                    });
+                });
+
+                ui.collapsing("Transform All", |ui| {
+                    let motors = self
+                        .variables
+                        .iter()
+                        .filter(|(_, variable)| driver_motor(variable.value).is_some())
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>();
+                    select_variable(
+                        ui,
+                        "transform_all_motor",
+                        "Motor:",
+                        &mut self.transform_all_motor,
+                        &motors,
+                    );
+                    ui.horizontal(|ui| {
+                        if let Some(motor) = &self.transform_all_motor
+                            && let Some(variable) = self.variables.get(motor)
+                        {
+                            if ui
+                                .button("Apply Motor")
+                                .on_hover_text(
+                                    "Adds a moved copy of every displayed point and line to the code",
+                                )
+                                .clicked()
+                            {
+                                transform_all = Some((motor.clone(), variable.value));
+                            }
+                        } else {
+                            ui.add_enabled(false, egui::Button::new("Apply Motor"));
+                        }
+                        if ui
+                            .button("Apply Camera Transform")
+                            .on_hover_text(
+                                "Adds a copy of every displayed point and line moved by the camera transform",
+                            )
+                            .clicked()
+                        {
+                            let motor = self.camera.transform.normalized();
+                            transform_all = Some((format!("({motor})"), motor));
+                        }
+                    });
+                });
+
+                ui.collapsing("Polygons", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Points:");
+                        ui.text_edit_singleline(&mut self.polygon_points)
+                            .on_hover_text("Point variable names separated by commas");
+                    });
+                    let points = self
+                        .polygon_points
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_string)
+                        .collect::<Vec<_>>();
+                    if points.len() >= 3 {
+                        if ui.button("Add Polygon").clicked() {
+                            self.polygons.push(Polygon {
+                                points,
+                                color: cgmath::Vector3 {
+                                    x: 0.0,
+                                    y: 0.5,
+                                    z: 1.0,
+                                },
+                                opacity: 0.4,
+                            });
+                            self.polygon_points.clear();
+                        }
+                    } else {
+                        ui.add_enabled(false, egui::Button::new("Add Polygon"))
+                            .on_disabled_hover_text("A polygon needs at least 3 points");
+                    }
+
+                    self.polygons.retain_mut(|polygon| {
+                        let mut delete = false;
+                        ui.horizontal(|ui| {
+                            ui.color_edit_button_rgb(polygon.color.as_mut());
+                            ui.add(
+                                egui::DragValue::new(&mut polygon.opacity)
+                                    .range(0.0..=1.0)
+                                    .speed(0.01)
+                                    .prefix("opacity: "),
+                            );
+                            ui.label(polygon.points.join(", "));
+                            delete = ui.button("Delete").clicked();
+                        });
+                        !delete
+                    });
                 });
-                ui.horizontal(|ui| {
-                    ui.label("Zoom Speed:");
-                    ui.add(egui::DragValue::new(&mut self.camera.zoom_speed).speed(0.1));
+
+                ui.collapsing("Measure", |ui| {
+                    let objects = self
+                        .variables
+                        .iter()
+                        .filter(|(_, variable)| {
+                            let line = variable.value.grade1();
+                            let point = variable.value.grade2();
+                            line.e0 != 0.0
+                                || line.e1 != 0.0
+                                || line.e2 != 0.0
+                                || point.e01 != 0.0
+                                || point.e02 != 0.0
+                                || point.e12 != 0.0
+                        })
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>();
+                    select_variable(
+                        ui,
+                        "measure_first",
+                        "First:",
+                        &mut self.measure_selection.0,
+                        &objects,
+                    );
+                    select_variable(
+                        ui,
+                        "measure_second",
+                        "Second:",
+                        &mut self.measure_selection.1,
+                        &objects,
+                    );
+                    ui.horizontal(|ui| {
+                        if let (Some(first), Some(second)) = &self.measure_selection
+                            && first != second
+                        {
+                            if ui.button("Measure").clicked() {
+                                self.measurement = Some((first.clone(), second.clone()));
+                            }
+                        } else {
+                            ui.add_enabled(false, egui::Button::new("Measure"));
+                        }
+                        if ui
+                            .add_enabled(self.measurement.is_some(), egui::Button::new("Clear"))
+                            .clicked()
+                        {
+                            self.measurement = None;
+                        }
+                    });
                 });
-                ui.horizontal(|ui| {
-                    ui.label("Line Thickness:");
-                    ui.add(egui::DragValue::new(&mut self.camera.line_thickness).speed(0.01));
+
+                ui.collapsing("Product By Grade", |ui| {
+                    let names = self.variables.keys().collect::<Vec<_>>();
+                    select_variable(
+                        ui,
+                        "product_first",
+                        "First:",
+                        &mut self.product_selection.0,
+                        &names,
+                    );
+                    select_variable(
+                        ui,
+                        "product_second",
+                        "Second:",
+                        &mut self.product_selection.1,
+                        &names,
+                    );
+                    ui.horizontal(|ui| {
+                        if let (Some(first), Some(second)) = &self.product_selection {
+                            if ui.button("Show Product").clicked() {
+                                self.product_decomposition = Some((first.clone(), second.clone()));
+                            }
+                        } else {
+                            ui.add_enabled(false, egui::Button::new("Show Product"));
+                        }
+                        if ui
+                            .add_enabled(
+                                self.product_decomposition.is_some(),
+                                egui::Button::new("Clear"),
+                            )
+                            .clicked()
+                        {
+                            self.product_decomposition = None;
+                        }
+                    });
+                    if let Some((first, second)) = &self.product_decomposition
+                        && let (Some(first_variable), Some(second_variable)) =
+                            (self.variables.get(first), self.variables.get(second))
+                    {
+                        let product = first_variable.value * second_variable.value;
+                        ui.label(format!("{first} * {second} = {product}"));
+                        for (grade, color) in PRODUCT_GRADE_COLORS.iter().enumerate() {
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "Grade {grade}: {}",
+                                    product.grade(grade)
+                                ))
+                                .color(color_to_egui(*color)),
+                            );
+                        }
+                    }
                 });
-                ui.horizontal(|ui| {
-                    ui.label("Point Radius:");
-                    ui.add(egui::DragValue::new(&mut self.camera.point_radius).speed(0.01));
+
+                let mut toggled_pin = None;
+
+                for name in &self.pinned_variables {
+                    if let Some(variable) = self.variables.get_mut(name)
+                        && edit_variable(
+                            ui,
+                            name,
+                            variable,
+                            &mut self.expanded_variables,
+                            true,
+                            self.angle_unit,
+                            self.scientific_notation,
+                            self.clean_values,
+                            self.display_defaults,
+                        )
+                    {
+                        toggled_pin = Some(name.clone());
+                    }
+                }
+
+                if !self.pinned_variables.is_empty() {
+                    ui.separator();
+                }
+
+                for (name, variable) in &mut self.variables {
+                    if self.pinned_variables.contains(name) || variable.group.is_some() {
+                        continue;
+                    }
+                    if edit_variable(
+                        ui,
+                        name,
+                        variable,
+                        &mut self.expanded_variables,
+                        false,
+                        self.angle_unit,
+                        self.scientific_notation,
+                        self.clean_values,
+                        self.display_defaults,
+                    ) {
+                        toggled_pin = Some(name.clone());
+                    }
+                }
+
+                let groups = self
+                    .variables
+                    .values()
+                    .filter_map(|variable| variable.group.clone())
+                    .collect::<BTreeSet<_>>();
+                for group in groups {
+                    egui::CollapsingHeader::new(format!("Group: {group}"))
+                        .id_salt(("group", &group))
+                        .show(ui, |ui| {
+                            let mut visible = !self.hidden_groups.contains(&group);
+                            if ui.checkbox(&mut visible, "Visible").changed() {
+                                if visible {
+                                    self.hidden_groups.remove(&group);
+                                } else {
+                                    self.hidden_groups.insert(group.clone());
+                                }
+                            }
+                            for (name, variable) in &mut self.variables {
+                                if self.pinned_variables.contains(name)
+                                    || variable.group.as_ref() != Some(&group)
+                                {
+                                    continue;
+                                }
+                                if edit_variable(
+                                    ui,
+                                    name,
+                                    variable,
+                                    &mut self.expanded_variables,
+                                    false,
+                                    self.angle_unit,
+                                    self.scientific_notation,
+                                    self.clean_values,
+                                    self.display_defaults,
+                                ) {
+                                    toggled_pin = Some(name.clone());
+                                }
+                            }
+                        });
+                }
+
+                if let Some(name) = toggled_pin {
+                    if let Some(index) = self.pinned_variables.iter().position(|n| *n == name) {
+                        self.pinned_variables.remove(index);
+                    } else {
+                        self.pinned_variables.push(name);
+                    }
+                }
+            });
+
+        if show_intersection
+            && let (Some(first), Some(second)) = &self.intersection_lines
+            && let (Some(first_line), Some(second_line)) =
+                (self.variables.get(first), self.variables.get(second))
+        {
+            let meet = first_line.value.wedge(second_line.value);
+            let name = self.unique_variable_name(meet_name(meet));
+            if !self.code.is_empty() && !self.code.ends_with('\n') {
+                self.code.push('\n');
+            }
+            self.code
+                .push_str(&format!("{name} = {first} ^ {second};\n"));
+            self.variables.insert(
+                name,
+                Variable {
+                    value: meet,
+                    frozen: None,
+                    group: None,
+                    display: Some(self.display_defaults.display()),
+                },
+            );
+            pending_evaluation |= true;
+        }
+
+        if let Some((motor_source, motor)) = transform_all {
+            let names = self
+                .variables
+                .iter()
+                .filter(|(_, variable)| {
+                    variable.display.is_some()
+                        && !is_hidden(&self.hidden_groups, variable)
+                        && (variable.value.grades(0.0)[1] || variable.value.grades(0.0)[2])
+                })
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            if !names.is_empty() && !self.code.is_empty() && !self.code.ends_with('\n') {
+                self.code.push('\n');
+            }
+            for name in names {
+                let variable = &self.variables[&name];
+                let copy = Variable {
+                    value: motor * variable.value * motor.reverse(),
+                    frozen: None,
+                    group: variable.group.clone(),
+                    display: variable.display.as_ref().map(|display| VariableDisplay {
+                        color: display.color,
+                        layer: display.layer,
+                        label: None,
+                        point_style: display.point_style,
+                        z_order: display.z_order,
+                    }),
+                };
+                let copy_name = self.unique_variable_name(&format!("{name}_moved"));
+                self.code.push_str(&format!(
+                    "{copy_name} = {motor_source} * {name} * ~{motor_source};\n"
+                ));
+                self.variables.insert(copy_name, copy);
+                pending_evaluation |= true;
+            }
+        }
+
+        if !ctx.wants_keyboard_input() {
+            ctx.input(|i| {
+                let mut move_direction = cgmath::Vector2 { x: 0.0, y: 0.0 };
+                move_direction.y += i.key_down(egui::Key::W) as u8 as f32;
+                move_direction.y -= i.key_down(egui::Key::S) as u8 as f32;
+                move_direction.x -= i.key_down(egui::Key::A) as u8 as f32;
+                move_direction.x += i.key_down(egui::Key::D) as u8 as f32;
+
+                let inf_point = Multivector {
+                    e1: move_direction.x,
+                    e2: move_direction.y,
+                    ..Multivector::ZERO
+                }
+                .wedge(Multivector {
+                    e0: 1.0,
+                    ..Multivector::ZERO
                 });
-                ui.horizontal(|ui| {
-                    ui.label("Flavour:");
-                    let mut flavour = GA_FLAVOUR.load(Ordering::Relaxed);
-                    let name = |flavour: u8| match flavour {
-                        0 => "Euclidean",
-                        1 => "Hyperbolic",
-                        2 => "Spherical",
-                        _ => unreachable!(),
+
+                let motor = Multivector::exp(
+                    inf_point.normalized()
+                        * (self.camera.move_speed * self.camera.view_height * dt * 0.5),
+                );
+
+                self.camera.transform = self.camera.transform * motor;
+
+                self.camera.view_height += i.key_down(egui::Key::Q) as u8 as f32
+                    * (self.camera.zoom_speed * self.camera.view_height * dt);
+                self.camera.view_height -= i.key_down(egui::Key::E) as u8 as f32
+                    * (self.camera.zoom_speed * self.camera.view_height * dt);
+            });
+        }
+
+        // this is applied on top of any manual movement, and rotating in the camera's own frame keeps the middle of the view fixed
+        if self.camera.spin {
+            self.camera.transform = (self.camera.transform
+                * Multivector::rotor(self.camera.spin_speed * dt))
+            .normalized();
+        }
+
+        if let Some(target) = self
+            .camera
+            .driver
+            .as_ref()
+            .and_then(|name| self.variables.get(name))
+            .and_then(|variable| driver_motor(variable.value))
+        {
+            self.camera.transform = follow_motor(
+                self.camera.transform,
+                target,
+                self.camera.driver_smoothing,
+                dt,
+            );
+        }
+
+        egui::CentralPanel::default()
+            .frame(egui::Frame::NONE.fill(self.theme.background()))
+            .show(ctx, |ui| {
+                let (rect, response) =
+                    ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+                let aspect = rect.width() / rect.height();
+
+                if response.drag_started()
+                    && let Some(position) = response.interact_pointer_pos()
+                {
+                    let pick_radius = (self.camera.point_radius * rect.height()
+                        / self.camera.view_height)
+                        .max(10.0);
+                    self.dragged_parameter = self
+                        .parameters
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, parameter)| {
+                            matches!(
+                                parameter.type_,
+                                ParameterType::Grade2 | ParameterType::Multivector
+                            ) && parameter.value.e12 != 0.0
+                        })
+                        .filter_map(|(i, parameter)| {
+                            let screen_position =
+                                world_to_screen(&self.camera, rect, parameter.value.grade2())?;
+                            Some((i, screen_position.distance(position)))
+                        })
+                        .filter(|&(_, distance)| distance <= pick_radius)
+                        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                        .map(|(i, _)| i);
+                }
+                if response.drag_stopped() {
+                    self.dragged_parameter = None;
+                }
+                let mut snapped_to = None;
+                if let Some(i) = self.dragged_parameter
+                    && self.camera.snap_to_points
+                    && !ui.input(|input| input.modifiers.alt)
+                    && let Some(parameter) = self.parameters.get(i)
+                    && let Some(position) = response.interact_pointer_pos()
+                {
+                    // the dragged point and everything computed from it move along with it,
+                    // so snapping to them would chase a moving target
+                    let moving = dependent_names(&self.code, &parameter.name);
+                    let candidates = self
+                        .variables
+                        .iter()
+                        .filter(|(name, variable)| {
+                            variable.display.is_some()
+                                && variable.value.is_finite()
+                                && variable.value.e12 != 0.0
+                                && !is_hidden(&self.hidden_groups, variable)
+                                && !moving.contains(name.as_str())
+                        })
+                        .filter_map(|(name, variable)| {
+                            let point = variable.value.grade2();
+                            Some((
+                                (name.clone(), point),
+                                world_to_screen(&self.camera, rect, point)?,
+                            ))
+                        });
+                    snapped_to = snap_target(position, candidates, POINT_SNAP_DISTANCE);
+                }
+                if let Some(i) = self.dragged_parameter
+                    && let Some(parameter) = self.parameters.get_mut(i)
+                    && let Some(position) = response.interact_pointer_pos()
+                    && let point = snapped_to.as_ref().map_or_else(
+                        || screen_to_world(&self.camera, rect, position),
+                        |&((_, point), _)| point,
+                    )
+                    && point.e12 != 0.0
+                {
+                    parameter.value = parameter.value - parameter.value.grade2()
+                        + point * (parameter.value.e12 / point.e12);
+                    pending_evaluation |= true;
+                }
+
+                pending_evaluation.run(|| self.update_code());
+
+                let mut objects = vec![];
+
+                for join in &self.joins {
+                    if let (Some(first), Some(second)) = (
+                        self.variables.get(&join.first),
+                        self.variables.get(&join.second),
+                    ) {
+                        let line = first.value.grade2().regressive(second.value.grade2());
+                        if line.is_finite() {
+                            objects.push(GpuObject {
+                                value: line,
+                                color: join.color,
+                                layer: 0.02,
+                                point_style: PointStyle::Disc as u32,
+                                scale: 1.0,
+                                z_order: 0,
+                            });
+                        }
+                    }
+                }
+
+                for orbit in &self.orbits {
+                    for point in orbit.points(&self.variables) {
+                        if point.is_finite() {
+                            objects.push(GpuObject {
+                                value: point,
+                                color: orbit.color,
+                                layer: 0.02,
+                                point_style: PointStyle::Disc as u32,
+                                scale: 1.0,
+                                z_order: 0,
+                            });
+                        }
+                    }
+                }
+
+                if let Some((first, second)) = &self.product_decomposition
+                    && let (Some(first), Some(second)) =
+                        (self.variables.get(first), self.variables.get(second))
+                {
+                    let product = first.value * second.value;
+                    for (grade, color) in PRODUCT_GRADE_COLORS.into_iter().enumerate() {
+                        let part = product.grade(grade);
+                        if part.is_finite() {
+                            objects.push(GpuObject {
+                                value: part,
+                                color,
+                                layer: 0.03,
+                                point_style: PointStyle::Disc as u32,
+                                scale: 1.0,
+                                z_order: 0,
+                            });
+                        }
+                    }
+                }
+
+                for variable in self
+                    .variables
+                    .values()
+                    .filter(|variable| !is_hidden(&self.hidden_groups, variable))
+                {
+                    if let Some(display) = &variable.display
+                        && variable.value.is_finite()
+                    {
+                        objects.push(GpuObject {
+                            value: variable.value,
+                            color: display.color,
+                            layer: display.layer,
+                            point_style: display.point_style as u32,
+                            scale: if self.camera.show_weight {
+                                weight_scale(variable.value)
+                            } else {
+                                1.0
+                            },
+                            z_order: display.z_order,
+                        });
+                    }
+                    // frozen copies are drawn as a dimmer ghost just behind the live value
+                    if let Some(display) = &variable.display
+                        && let Some(frozen) = variable.frozen
+                        && frozen.is_finite()
+                    {
+                        objects.push(GpuObject {
+                            value: frozen,
+                            color: display.color * 0.4,
+                            layer: display.layer - 0.001,
+                            point_style: display.point_style as u32,
+                            scale: if self.camera.show_weight {
+                                weight_scale(frozen)
+                            } else {
+                                1.0
+                            },
+                            z_order: display.z_order,
+                        });
+                    }
+                }
+
+                self.camera.view_height = self
+                    .camera
+                    .view_height
+                    .clamp(0.1, self.camera.max_view_height.max(0.1));
+
+                let mut polygons = vec![];
+                for polygon in &self.polygons {
+                    let Some(positions) = polygon
+                        .points
+                        .iter()
+                        .map(|name| {
+                            let point = self.variables.get(name)?.value.grade2();
+                            let position = world_to_screen(&self.camera, rect, point)?;
+                            Some(cgmath::Vector2 {
+                                x: (position.x - rect.left()) / rect.width() * 2.0 - 1.0,
+                                y: 1.0 - (position.y - rect.top()) / rect.height() * 2.0,
+                            })
+                        })
+                        .collect::<Option<Vec<_>>>()
+                    else {
+                        continue;
                     };
-                    if egui::ComboBox::from_id_salt("flavour")
-                        .selected_text(name(flavour))
-                        .show_ui(ui, |ui| {
-                            let mut changed = false;
-                            changed |= ui.selectable_value(&mut flavour, 0, name(0)).changed();
-                            changed |= ui.selectable_value(&mut flavour, 1, name(1)).changed();
-                            changed |= ui.selectable_value(&mut flavour, 2, name(2)).changed();
-                            changed
+                    let color = polygon.color.extend(polygon.opacity);
+                    // a triangle fan around the first point, so this is only exact for convex polygons
+                    for window in positions.get(1..).unwrap_or_default().windows(2) {
+                        for position in [positions[0], window[0], window[1]] {
+                            polygons.push(GpuPolygonVertex { position, color });
+                        }
+                    }
+                }
+
+                if self.gpu_error.is_none() {
+                    ui.painter()
+                        .add(eframe::egui_wgpu::Callback::new_paint_callback(
+                            rect,
+                            RenderData {
+                                camera: GpuCamera {
+                                    transform: self.camera.transform,
+                                    vertical_height: self.camera.view_height,
+                                    aspect,
+                                    line_thickness: self.camera.line_thickness,
+                                    point_radius: self.camera.point_radius,
+                                    flavour: GA_FLAVOUR.load(Ordering::Relaxed) as u32,
+                                    line_extent: self.camera.line_extent,
+                                    outline_width: if self.camera.outlines {
+                                        self.camera.outline_width * self.camera.view_height
+                                            / rect.height()
+                                    } else {
+                                        0.0
+                                    },
+                                    line_orientation: self.camera.line_orientation as u32,
+                                },
+                                objects,
+                                polygons,
+                            },
+                        ));
+                }
+
+                if export_svg {
+                    ui.ctx().copy_text(scene_to_svg(self, rect));
+                }
+                if bake_frames {
+                    self.bake_status = Some(self.bake_frames(rect));
+                }
+
+                let view_center = screen_to_world(&self.camera, rect, rect.center());
+                for variable in self
+                    .variables
+                    .values()
+                    .filter(|variable| !is_hidden(&self.hidden_groups, variable))
+                {
+                    if let Some(display) = &variable.display
+                        && let Some(label) = &display.label
+                        && let Some(anchor) = label_anchor(variable.value, view_center)
+                        && let Some(position) = world_to_screen(&self.camera, rect, anchor)
+                        && rect.contains(position)
+                    {
+                        ui.painter().text(
+                            position + egui::vec2(6.0, -6.0),
+                            egui::Align2::LEFT_BOTTOM,
+                            label,
+                            egui::FontId::proportional(14.0),
+                            color_to_egui(display.color),
+                        );
+                    }
+                }
+
+                if let Some((first, second)) = &self.measurement
+                    && let (Some(first), Some(second)) =
+                        (self.variables.get(first), self.variables.get(second))
+                {
+                    let (angle, distance) = measure(first.value, second.value);
+                    let first_position = label_anchor(first.value, view_center)
+                        .and_then(|anchor| world_to_screen(&self.camera, rect, anchor));
+                    let second_position = label_anchor(second.value, view_center)
+                        .and_then(|anchor| world_to_screen(&self.camera, rect, anchor));
+                    if let (Some(first_position), Some(second_position)) =
+                        (first_position, second_position)
+                    {
+                        ui.painter().line_segment(
+                            [first_position, second_position],
+                            egui::Stroke::new(1.0, egui::Color32::YELLOW),
+                        );
+                        let mut text = vec![];
+                        if let Some(angle) = angle {
+                            text.push(format!("Angle: {}", self.angle_unit.format(angle)));
+                        }
+                        if let Some(distance) = distance {
+                            text.push(format!(
+                                "Distance: {}",
+                                format_readout(distance, self.scientific_notation)
+                            ));
+                        }
+                        ui.painter().text(
+                            first_position.lerp(second_position, 0.5) + egui::vec2(0.0, -6.0),
+                            egui::Align2::CENTER_BOTTOM,
+                            text.join("\n"),
+                            egui::FontId::monospace(12.0),
+                            egui::Color32::YELLOW,
+                        );
+                    }
+                }
+
+                if let Some((first, second)) = &self.product_decomposition
+                    && let (Some(first), Some(second)) =
+                        (self.variables.get(first), self.variables.get(second))
+                {
+                    let product = first.value * second.value;
+                    for (grade, color) in PRODUCT_GRADE_COLORS.into_iter().enumerate() {
+                        if let Some(anchor) = label_anchor(product.grade(grade), view_center)
+                            && let Some(position) = world_to_screen(&self.camera, rect, anchor)
+                            && rect.contains(position)
+                        {
+                            ui.painter().text(
+                                position + egui::vec2(6.0, -6.0),
+                                egui::Align2::LEFT_BOTTOM,
+                                format!("grade {grade}"),
+                                egui::FontId::proportional(14.0),
+                                color_to_egui(color),
+                            );
+                        }
+                    }
+                }
+
+                for orbit in &self.orbits {
+                    let positions = orbit
+                        .points(&self.variables)
+                        .into_iter()
+                        .filter_map(|point| world_to_screen(&self.camera, rect, point))
+                        .collect::<Vec<_>>();
+                    ui.painter().add(egui::Shape::line(
+                        positions,
+                        egui::Stroke::new(1.0, color_to_egui(orbit.color)),
+                    ));
+                }
+
+                if self.camera.gizmo {
+                    let center = rect.center();
+                    let units_per_pixel = self.camera.view_height / rect.height();
+                    for handle in [
+                        GizmoHandle::TranslateX,
+                        GizmoHandle::TranslateY,
+                        GizmoHandle::Rotate,
+                    ] {
+                        let position = center + handle.offset();
+                        let handle_response = ui.interact(
+                            egui::Rect::from_center_size(position, egui::vec2(16.0, 16.0)),
+                            ui.id().with(("gizmo", handle as u8)),
+                            egui::Sense::drag(),
+                        );
+                        if handle_response.drag_started() {
+                            self.gizmo_drag_start = Some(self.camera.transform);
+                        }
+                        if let Some(current) = handle_response.interact_pointer_pos()
+                            && handle_response.dragged()
+                        {
+                            // rotations are measured from where the drag started so that they can be
+                            // snapped, holding shift snaps the camera's total rotation to the increment
+                            if handle == GizmoHandle::Rotate
+                                && let Some(start) = self.gizmo_drag_start
+                                && let Some(origin) = ui.input(|i| i.pointer.press_origin())
+                            {
+                                let mut angle = swept_angle(center, origin, current);
+                                if ui.input(|i| i.modifiers.shift) {
+                                    let start_angle = start.rotation_angle();
+                                    angle = snap_angle(start_angle + angle, self.camera.snap_angle)
+                                        - start_angle;
+                                }
+                                self.camera.transform =
+                                    (start * Multivector::rotor(angle)).normalized();
+                            } else {
+                                let previous = current - handle_response.drag_delta();
+                                self.camera.transform = (self.camera.transform
+                                    * handle.motor(center, previous, current, units_per_pixel))
+                                .normalized();
+                            }
+                        }
+
+                        let color = if handle_response.hovered() || handle_response.dragged() {
+                            self.theme.overlay_color()
+                        } else {
+                            handle.color()
+                        };
+                        match handle {
+                            GizmoHandle::TranslateX | GizmoHandle::TranslateY => {
+                                ui.painter().arrow(
+                                    center,
+                                    handle.offset(),
+                                    egui::Stroke::new(2.0, color),
+                                );
+                            }
+                            GizmoHandle::Rotate => {
+                                ui.painter().circle_stroke(
+                                    center,
+                                    handle.offset().length(),
+                                    egui::Stroke::new(1.0, color),
+                                );
+                            }
+                        }
+                        ui.painter().circle_filled(position, 5.0, color);
+                    }
+                }
+
+                if self.camera.ideal_line && GA_FLAVOUR.load(Ordering::Relaxed) == 0 {
+                    let border = rect.shrink(IDEAL_LINE_INSET);
+                    ui.painter().rect_stroke(
+                        border,
+                        0.0,
+                        egui::Stroke::new(2.0, egui::Color32::from_gray(160)),
+                        egui::StrokeKind::Middle,
+                    );
+                    for variable in self
+                        .variables
+                        .values()
+                        .filter(|variable| !is_hidden(&self.hidden_groups, variable))
+                    {
+                        if let Some(display) = &variable.display
+                            && variable.value.is_finite()
+                            && let Some(position) =
+                                ideal_point_on_border(&self.camera, border, variable.value.grade2())
+                        {
+                            ui.painter()
+                                .circle_filled(position, 6.0, color_to_egui(display.color));
+                        }
+                    }
+                }
+
+                if self.camera.layer_badges {
+                    for variable in self
+                        .variables
+                        .values()
+                        .filter(|variable| !is_hidden(&self.hidden_groups, variable))
+                    {
+                        if let Some(display) = &variable.display
+                            && variable.value.is_finite()
+                            && let Some(anchor) = label_anchor(variable.value, view_center)
+                            && let Some(position) = world_to_screen(&self.camera, rect, anchor)
+                            && rect.contains(position)
+                        {
+                            ui.painter().text(
+                                position + egui::vec2(6.0, 6.0),
+                                egui::Align2::LEFT_TOP,
+                                format!("{:.2}", display.layer),
+                                egui::FontId::monospace(10.0),
+                                self.theme.overlay_color().gamma_multiply(0.8),
+                            );
+                        }
+                    }
+                }
+
+                if let Some(((name, _), position)) = snapped_to {
+                    ui.painter().circle_stroke(
+                        position,
+                        POINT_SNAP_DISTANCE,
+                        egui::Stroke::new(1.5, egui::Color32::YELLOW),
+                    );
+                    ui.painter().text(
+                        position + egui::vec2(POINT_SNAP_DISTANCE, POINT_SNAP_DISTANCE),
+                        egui::Align2::LEFT_TOP,
+                        format!("snap: {name}"),
+                        egui::FontId::monospace(10.0),
+                        egui::Color32::YELLOW,
+                    );
+                }
+
+                if self.camera.coincident_badges {
+                    let displayed = self
+                        .variables
+                        .iter()
+                        .filter(|(_, variable)| {
+                            variable.display.is_some()
+                                && variable.value.is_finite()
+                                && !is_hidden(&self.hidden_groups, variable)
                         })
-                        .inner
-                        .unwrap_or(false)
+                        .map(|(name, variable)| (name, variable.value));
+                    for group in coincident_groups(displayed) {
+                        if let Some(anchor) =
+                            label_anchor(self.variables[group[0]].value, view_center)
+                            && let Some(position) = world_to_screen(&self.camera, rect, anchor)
+                            && rect.contains(position)
+                        {
+                            ui.painter().text(
+                                position + egui::vec2(6.0, -6.0),
+                                egui::Align2::LEFT_BOTTOM,
+                                format!(
+                                    "×{}: {}",
+                                    group.len(),
+                                    group
+                                        .iter()
+                                        .map(|name| name.as_str())
+                                        .collect::<Vec<_>>()
+                                        .join(", ")
+                                ),
+                                egui::FontId::monospace(10.0),
+                                egui::Color32::YELLOW,
+                            );
+                        }
+                    }
+                }
+
+                if let Some(position) = response.hover_pos() {
+                    let text =
+                        match screen_to_world(&self.camera, rect, position).point_coordinates() {
+                            Some((x, y)) => format!(
+                                "({}, {})",
+                                format_readout(x, self.scientific_notation),
+                                format_readout(y, self.scientific_notation)
+                            ),
+                            None => "(ideal)".into(),
+                        };
+                    ui.painter().text(
+                        rect.left_top() + egui::vec2(8.0, 8.0),
+                        egui::Align2::LEFT_TOP,
+                        text,
+                        egui::FontId::monospace(14.0),
+                        self.theme.overlay_color(),
+                    );
+                }
+            });
+
+        egui::Window::new("Changes")
+            .open(&mut self.changes_window_open)
+            .show(ctx, |ui| {
+                let Some(snapshot) = &self.saved_snapshot else {
+                    ui.label("There is no saved scene to compare against");
+                    return;
+                };
+
+                for change in snapshot.changes(&self.parameters, &self.variables, &self.code) {
+                    ui.label(change);
+                }
+            });
+
+        if self.auto_save_interval > 0.0
+            && (time - *self.last_save.get_or_insert(time)).as_secs_f32() >= self.auto_save_interval
+            && let Some(storage) = frame.storage_mut()
+        {
+            let save_start = std::time::Instant::now();
+            eframe::App::save(self, storage);
+            storage.flush();
+            self.last_save_duration = Some(save_start.elapsed());
+            self.last_save = Some(time);
+        }
+
+        ctx.request_repaint();
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        storage.set_string("App", ron::to_string(self).unwrap());
+        storage.set_string(
+            "Flavour",
+            ron::to_string(&GA_FLAVOUR.load(Ordering::Relaxed)).unwrap(),
+        );
+        storage.set_string(
+            "FlippedOrientation",
+            ron::to_string(&FLIPPED_ORIENTATION.load(Ordering::Relaxed)).unwrap(),
+        );
+    }
+}
+
+fn screen_to_world(camera: &Camera, rect: egui::Rect, position: egui::Pos2) -> Multivector {
+    let uv = cgmath::Vector2 {
+        x: (position.x - rect.left()) / rect.width() * 2.0 - 1.0,
+        y: 1.0 - (position.y - rect.top()) / rect.height() * 2.0,
+    };
+    let aspect = rect.width() / rect.height();
+
+    let mut transform = camera.transform;
+    let pixel_distance = uv.magnitude();
+    if pixel_distance > 0.0 {
+        let pixel_direction = uv / pixel_distance;
+        let pixel_line = Multivector {
+            e1: pixel_direction.x * aspect,
+            e2: pixel_direction.y,
+            ..Multivector::ZERO
+        };
+        let inf_point = pixel_line.wedge(Multivector {
+            e0: 1.0,
+            ..Multivector::ZERO
+        });
+        let pixel_rotor =
+            Multivector::exp(inf_point * (pixel_distance * camera.view_height * 0.25)).normalized();
+        transform = transform * pixel_rotor;
+    }
+
+    let transform = transform.normalized();
+    (transform
+        * Multivector {
+            e12: 1.0,
+            ..Multivector::ZERO
+        }
+        * transform.reverse())
+    .normalized()
+}
+
+/// What to call the meet of two lines, parallel lines meet at an ideal point which is a direction
+fn meet_name(meet: Multivector) -> &'static str {
+    if meet.point_coordinates().is_none() {
+        "direction"
+    } else {
+        "intersection"
+    }
+}
+
+/// The normalized motor in `value` for the camera to follow, `None` if it is not a motor
+fn driver_motor(value: Multivector) -> Option<Multivector> {
+    let even = value.grade0() + value.grade2();
+    (even == value && value.is_versor(VERSOR_EPSILON)).then(|| value.normalized())
+}
+
+/// Moves `transform` towards `target`, taking about `smoothing` seconds to get most of the way there
+fn follow_motor(
+    transform: Multivector,
+    target: Multivector,
+    smoothing: f32,
+    dt: f32,
+) -> Multivector {
+    if smoothing <= 0.0 {
+        return target;
+    }
+    // `target` and `-target` are the same motor, so blend towards whichever is closer
+    let target = if (transform * target.reverse()).s < 0.0 {
+        -target
+    } else {
+        target
+    };
+    let t = 1.0 - (-dt / smoothing).exp();
+    (transform + (target - transform) * t).normalized()
+}
+
+/// Moves the camera so that the euclidean point `(x, y)` is at the center of the screen,
+/// keeping its current orientation
+fn recentered_transform(camera: &Camera, x: f32, y: f32) -> Multivector {
+    let transform = camera.transform.normalized();
+    let (center_x, center_y) = (transform
+        * Multivector {
+            e12: 1.0,
+            ..Multivector::ZERO
+        }
+        * transform.reverse())
+    .point_coordinates()
+    .unwrap_or((0.0, 0.0));
+    (Multivector::translator(x - center_x, y - center_y) * transform).normalized()
+}
+
+fn world_to_screen(camera: &Camera, rect: egui::Rect, point: Multivector) -> Option<egui::Pos2> {
+    let transform = camera.transform.normalized();
+    let mut relative = transform.reverse() * point * transform;
+    let flavour = GA_FLAVOUR.load(Ordering::Relaxed);
+    // points on the sphere are not identified with their antipodes, so only flip the sign otherwise
+    if flavour != 2 && relative.e12 < 0.0 {
+        relative = -relative;
+    }
+
+    let offset = cgmath::Vector2 {
+        x: -relative.e02,
+        y: relative.e01,
+    };
+    let offset_length = offset.magnitude();
+    let distance = match flavour {
+        0 => {
+            if relative.e12 == 0.0 {
+                return None;
+            }
+            offset_length / relative.e12
+        }
+        1 => {
+            let ratio = offset_length / relative.e12;
+            if ratio.is_nan() || ratio >= 1.0 {
+                return None;
+            }
+            ratio.atanh()
+        }
+        2 => offset_length.atan2(relative.e12),
+        _ => unreachable!(),
+    };
+
+    let aspect = rect.width() / rect.height();
+    let uv = if offset_length > 0.0 {
+        let pixel_direction = cgmath::Vector2 {
+            x: offset.x / aspect,
+            y: offset.y,
+        }
+        .normalize();
+        let pixel_line_length = cgmath::Vector2 {
+            x: pixel_direction.x * aspect,
+            y: pixel_direction.y,
+        }
+        .magnitude();
+        pixel_direction * (distance / (camera.view_height * 0.5 * pixel_line_length))
+    } else {
+        cgmath::Vector2 { x: 0.0, y: 0.0 }
+    };
+
+    Some(egui::pos2(
+        rect.left() + (uv.x + 1.0) * 0.5 * rect.width(),
+        rect.top() + (1.0 - uv.y) * 0.5 * rect.height(),
+    ))
+}
+
+/// Comments out every line touched by `selection` with `// `, unless they are all already comments
+/// in which case they are uncommented, blank lines are left alone either way
+fn toggle_line_comments(code: &str, selection: std::ops::Range<usize>) -> String {
+    let start = code[..selection.start].rfind('\n').map_or(0, |i| i + 1);
+    // a selection ending at the very start of a line does not include that line
+    let end_search = if selection.end > selection.start && code[..selection.end].ends_with('\n') {
+        selection.end - 1
+    } else {
+        selection.end
+    };
+    let end = code[end_search..]
+        .find('\n')
+        .map_or(code.len(), |i| end_search + i);
+
+    let lines = code[start..end].split('\n').collect::<Vec<_>>();
+    let comment = lines
+        .iter()
+        .any(|line| !line.trim().is_empty() && !line.trim_start().starts_with("//"));
+    let lines = lines
+        .into_iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                line.to_string()
+            } else if comment {
+                format!("// {line}")
+            } else {
+                let indent = line.len() - line.trim_start().len();
+                let rest = &line[indent + 2..];
+                format!(
+                    "{}{}",
+                    &line[..indent],
+                    rest.strip_prefix(' ').unwrap_or(rest)
+                )
+            }
+        })
+        .collect::<Vec<_>>();
+
+    format!("{}{}{}", &code[..start], lines.join("\n"), &code[end..])
+}
+
+/// The location of the byte `position` in `code`
+fn location_at(code: &str, position: usize) -> Location {
+    let prefix = &code[..position];
+    Location {
+        position,
+        line: prefix.matches('\n').count() + 1,
+        column: prefix
+            .rsplit('\n')
+            .next()
+            .map_or(0, |line| line.chars().count())
+            + 1,
+    }
+}
+
+/// The byte range of the statement that `cursor` is in, statements are split on semicolons that are not in comments.
+/// A cursor just after a semicolon is still in the statement that the semicolon ends
+fn statement_under_cursor(code: &str, cursor: usize) -> std::ops::Range<usize> {
+    let mut start = 0;
+    let mut line_start = 0;
+    for line in code.split_inclusive('\n') {
+        let line_code = line.find("//").map_or(line, |comment| &line[..comment]);
+        for (i, _) in line_code.match_indices(';') {
+            let end = line_start + i + 1;
+            if end >= cursor {
+                return start..end;
+            }
+            start = end;
+        }
+        line_start += line.len();
+    }
+    start..code.len()
+}
+
+/// Evaluates the statement under `cursor` against `variables` without assigning anything,
+/// giving the formatted result or the first error, or `None` if there is nothing to evaluate.
+/// A statement can also be a bare expression, and does not need its semicolon yet
+fn preview_statement(
+    code: &str,
+    cursor: usize,
+    variables: &BTreeMap<String, Variable>,
+    settings: ScriptSettings,
+) -> Option<Result<String, String>> {
+    let range = statement_under_cursor(code, cursor);
+    let base = location_at(code, range.start);
+    let source = code[range].trim_end();
+    let source = source.strip_suffix(';').unwrap_or(source);
+    if source.lines().all(|line| {
+        let line = line.trim();
+        line.is_empty() || line.starts_with("//")
+    }) {
+        return None;
+    }
+
+    // a semicolon after a trailing comment would be commented out too
+    let statement_source = if source
+        .lines()
+        .last()
+        .is_some_and(|line| line.contains("//"))
+    {
+        format!("{source}\n;")
+    } else {
+        format!("{source};")
+    };
+    let (name, expression) = match parse(&statement_source) {
+        Ok(mut statements) if statements.len() == 1 => match statements.remove(0).kind {
+            AstStatementKind::Assignment { name, value, .. } => (Some(name), value),
+        },
+        statement_result => match parse_expression(source) {
+            Ok(expression) => (None, expression),
+            Err(error) => {
+                let error = statement_result.err().unwrap_or(error);
+                return Some(Err(format!(
+                    "{}: {}",
+                    offset_location(base, error.location),
+                    error.kind
+                )));
+            }
+        },
+    };
+
+    let outcome = evaluate_statement(
+        &expression,
+        expression.location,
+        variables,
+        &HashMap::new(),
+        |name| variables.contains_key(name),
+        settings,
+        &mut settings.budget.clone(),
+    );
+    Some(match outcome.result {
+        StatementResult::Value(value) => Ok(match name {
+            Some(name) => format!("{name} = {value}"),
+            None => value.to_string(),
+        }),
+        StatementResult::Error(error) | StatementResult::OutOfBudget(error) => Err(format!(
+            "{}: {}",
+            offset_location(base, error.location),
+            error.message
+        )),
+        // nothing is assigned by other statements, so nothing can be skipped
+        StatementResult::Skipped => return None,
+    })
+}
+
+/// Checks that every name `value` reads is assigned before it runs, `Err(None)` meaning a name
+/// comes from an earlier statement that failed, which is already reported
+fn unassigned_error(
+    value: &AstExpression<'_>,
+    statement_location: Location,
+    assignment_locations: &HashMap<&str, Location>,
+    is_assigned: impl Fn(&str) -> bool,
+) -> Result<(), Option<Diagnostic>> {
+    // statements run top to bottom, so only names assigned so far can be read,
+    // otherwise the value left over from the previous evaluation would be used
+    let mut unassigned = None;
+    value.for_each_name(&mut |name, name_token| {
+        if unassigned.is_none() && !is_assigned(name) {
+            unassigned = Some((name, name_token.location, name_token.end));
+        }
+    });
+    let Some((unassigned_name, location, end)) = unassigned else {
+        return Ok(());
+    };
+    Err(match assignment_locations.get(unassigned_name) {
+        // it was assigned earlier but that statement failed, which is already reported
+        Some(assignment) if assignment.position < statement_location.position => None,
+        Some(assignment) => Some(Diagnostic {
+            location,
+            end: Some(end),
+            message: format!(
+                "'{unassigned_name}' used before assignment on line {}",
+                assignment.line
+            ),
+        }),
+        None => Some(Diagnostic {
+            location,
+            end: Some(end),
+            message: format!("Unknown variable '{unassigned_name}'"),
+        }),
+    })
+}
+
+/// Collects every change to the code or to the values it reads during a frame, with `|=`,
+/// so the code is evaluated at most once per frame however many changes there were
+#[derive(Default)]
+struct PendingEvaluation {
+    requested: bool,
+}
+
+impl std::ops::BitOrAssign<bool> for PendingEvaluation {
+    fn bitor_assign(&mut self, changed: bool) {
+        self.requested |= changed;
+    }
+}
+
+impl PendingEvaluation {
+    /// Calls `evaluate` if anything changed since the last run
+    fn run(&mut self, evaluate: impl FnOnce()) {
+        if std::mem::take(&mut self.requested) {
+            evaluate();
+        }
+    }
+}
+
+/// How scripts are evaluated, from the settings in the Code window
+#[derive(Clone, Copy)]
+struct ScriptSettings {
+    angle_unit: AngleUnit,
+    strict_scalars: bool,
+    budget: usize,
+}
+
+/// What running one statement did
+#[derive(Debug, PartialEq)]
+enum StatementResult {
+    Value(Multivector),
+    Error(Diagnostic),
+    /// The evaluation budget ran out, so none of the later statements run either
+    OutOfBudget(Diagnostic),
+    /// It reads a name from an earlier statement that failed, which is already reported
+    Skipped,
+}
+
+#[derive(Debug, PartialEq)]
+struct StatementOutcome {
+    warnings: Vec<Diagnostic>,
+    result: StatementResult,
+}
+
+/// The names that are set before any statement runs, the time and every driver and parameter
+fn input_names<'a>(drivers: &'a [Driver], parameters: &'a [Parameter]) -> HashSet<&'a str> {
+    std::iter::once(TIME_VARIABLE)
+        .chain(drivers.iter().map(|driver| driver.name.as_str()))
+        .chain(parameters.iter().map(|parameter| parameter.name.as_str()))
+        .collect()
+}
+
+/// Where each name is first assigned
+fn assignment_locations<'source>(
+    statements: &[AstStatement<'source>],
+) -> HashMap<&'source str, Location> {
+    let mut assignment_locations = HashMap::new();
+    for statement in statements {
+        match statement.kind {
+            AstStatementKind::Assignment { name, .. } => {
+                assignment_locations
+                    .entry(name)
+                    .or_insert(statement.location);
+            }
+        }
+    }
+    assignment_locations
+}
+
+/// Sets `name` in `scope`, adding it if it is new
+fn assign(scope: &mut BTreeMap<String, Variable>, name: &str, value: Multivector) {
+    scope
+        .entry(name.into())
+        .or_insert_with(|| Variable {
+            value: Multivector::ZERO,
+            frozen: None,
+            group: None,
+            display: None,
+        })
+        .value = value;
+}
+
+/// Checks that `value` only reads names `is_assigned` accepts, then evaluates it against `scope`
+/// without assigning anything
+fn evaluate_statement<'source>(
+    value: &AstExpression<'source>,
+    statement_location: Location,
+    scope: &BTreeMap<String, Variable>,
+    assignment_locations: &HashMap<&str, Location>,
+    is_assigned: impl Fn(&str) -> bool,
+    settings: ScriptSettings,
+    budget: &mut usize,
+) -> StatementOutcome {
+    if let Err(error) =
+        unassigned_error(value, statement_location, assignment_locations, is_assigned)
+    {
+        return StatementOutcome {
+            warnings: vec![],
+            result: error.map_or(StatementResult::Skipped, StatementResult::Error),
+        };
+    }
+
+    let mut warnings = vec![];
+    let result = match evaluate_expression(
+        value,
+        scope,
+        settings.angle_unit,
+        settings.strict_scalars,
+        &mut warnings,
+        budget,
+    ) {
+        Ok(value) => StatementResult::Value(value),
+        Err(
+            error @ EvaluationError {
+                kind: EvaluationErrorKind::BudgetExceeded,
+                ..
+            },
+        ) => StatementResult::OutOfBudget(error.into()),
+        Err(error) => StatementResult::Error(error.into()),
+    };
+    StatementOutcome {
+        warnings: warnings.into_iter().map(Diagnostic::from).collect(),
+        result,
+    }
+}
+
+/// Runs `statements` top to bottom, assigning into `scope`. A statement can only read names in `assigned`,
+/// which gains each name as it is assigned, otherwise it would read a value left over from an earlier run.
+/// Stops once the budget runs out, so there can be fewer outcomes than statements
+fn evaluate_statements<'source>(
+    statements: &[AstStatement<'source>],
+    scope: &mut BTreeMap<String, Variable>,
+    assigned: &mut HashSet<&'source str>,
+    settings: ScriptSettings,
+) -> Vec<StatementOutcome> {
+    let assignment_locations = assignment_locations(statements);
+    let mut budget = settings.budget;
+    let mut outcomes = vec![];
+    for statement in statements {
+        match statement.kind {
+            AstStatementKind::Assignment {
+                name, ref value, ..
+            } => {
+                let outcome = evaluate_statement(
+                    value,
+                    statement.location,
+                    scope,
+                    &assignment_locations,
+                    |name| assigned.contains(name),
+                    settings,
+                    &mut budget,
+                );
+                let out_of_budget = matches!(outcome.result, StatementResult::OutOfBudget(_));
+                if let StatementResult::Value(value) = outcome.result {
+                    assign(scope, name, value);
+                    assigned.insert(name);
+                }
+                outcomes.push(outcome);
+                // the rest of the statements would all fail the same way
+                if out_of_budget {
+                    break;
+                }
+            }
+        }
+    }
+    outcomes
+}
+
+/// Levels with fewer statements than this are evaluated on the current thread
+const MIN_PARALLEL_STATEMENTS: usize = 64;
+
+/// Does the same as [`evaluate_statements`], but in levels, where each statement only reads names assigned
+/// in earlier levels, running every level on several threads against `scope` as it was when the level started.
+/// Returns `None` without changing anything when the outcome could differ from running in order:
+/// a name is assigned more than once, or the code could run out of budget at a point that depends on the order
+fn evaluate_in_parallel<'source>(
+    statements: &[AstStatement<'source>],
+    scope: &mut BTreeMap<String, Variable>,
+    assigned: &mut HashSet<&'source str>,
+    settings: ScriptSettings,
+) -> Option<Vec<StatementOutcome>> {
+    let mut assigned_at = HashMap::new();
+    let mut steps = 0usize;
+    for (index, statement) in statements.iter().enumerate() {
+        match statement.kind {
+            AstStatementKind::Assignment {
+                name, ref value, ..
+            } => {
+                if assigned.contains(name) || assigned_at.insert(name, index).is_some() {
+                    return None;
+                }
+                steps = steps.saturating_add(value.node_count());
+            }
+        }
+    }
+    if steps > settings.budget {
+        return None;
+    }
+
+    let mut levels: Vec<Vec<usize>> = vec![];
+    let mut statement_levels = Vec::with_capacity(statements.len());
+    for (index, statement) in statements.iter().enumerate() {
+        let mut level = 0;
+        match statement.kind {
+            AstStatementKind::Assignment { ref value, .. } => {
+                value.for_each_name(&mut |name, _| {
+                    if let Some(&assignment) = assigned_at.get(name)
+                        && assignment < index
                     {
-                        GA_FLAVOUR.store(flavour, Ordering::Relaxed);
+                        level = level.max(statement_levels[assignment] + 1);
                     }
                 });
-            });
+            }
+        }
+        statement_levels.push(level);
+        if levels.len() <= level {
+            levels.resize_with(level + 1, Vec::new);
+        }
+        levels[level].push(index);
+    }
 
-        egui::Window::new("Parameters")
-            .open(&mut self.parameters_window_open)
-            .resizable(true)
-            .show(ctx, |ui| {
-                if ui.button("New Parameter").clicked() {
-                    self.parameters.push(Parameter {
-                        name: "unnamed".into(),
-                        type_: ParameterType::Grade0,
-                        value: Multivector::ZERO,
-                    });
-                    code_or_parameters_changed = true;
+    let assignment_locations = assignment_locations(statements);
+    let threads = std::thread::available_parallelism().map_or(1, std::num::NonZero::get);
+    let mut outcomes: Vec<Option<StatementOutcome>> = statements.iter().map(|_| None).collect();
+    for level in levels {
+        let snapshot = &*scope;
+        let readable = &*assigned;
+        let evaluate = |&index: &usize| {
+            let outcome = match statements[index].kind {
+                AstStatementKind::Assignment { ref value, .. } => evaluate_statement(
+                    value,
+                    statements[index].location,
+                    snapshot,
+                    &assignment_locations,
+                    |name| {
+                        // names from this level or later are already in `readable` if they come
+                        // before this statement in a lower level, so the order has to be checked too
+                        readable.contains(name)
+                            && assigned_at.get(name).is_none_or(|&at| at < index)
+                    },
+                    settings,
+                    // the whole script fits in the budget, so no statement can run out
+                    &mut settings.budget.clone(),
+                ),
+            };
+            (index, outcome)
+        };
+        let evaluated: Vec<_> = if threads > 1 && level.len() >= MIN_PARALLEL_STATEMENTS {
+            std::thread::scope(|thread_scope| {
+                let handles: Vec<_> = level
+                    .chunks(level.len().div_ceil(threads))
+                    .map(|chunk| {
+                        thread_scope.spawn(move || chunk.iter().map(evaluate).collect::<Vec<_>>())
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| {
+                        handle
+                            .join()
+                            .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+                    })
+                    .collect()
+            })
+        } else {
+            level.iter().map(evaluate).collect()
+        };
+
+        for (index, outcome) in evaluated {
+            if let StatementResult::Value(value) = outcome.result {
+                match statements[index].kind {
+                    AstStatementKind::Assignment { name, .. } => {
+                        assign(scope, name, value);
+                        assigned.insert(name);
+                    }
                 }
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    let mut i = 0usize;
-                    let mut delete = false;
-                    self.parameters.retain_mut(|parameter| {
-                        egui::CollapsingHeader::new(&parameter.name)
-                            .id_salt(i)
-                            .show(ui, |ui| {
-                                ui.horizontal(|ui| {
-                                    ui.label("Name:");
-                                    code_or_parameters_changed |=
-                                        ui.text_edit_singleline(&mut parameter.name).changed();
-                                });
+            }
+            outcomes[index] = Some(outcome);
+        }
+    }
 
-                                ui.horizontal(|ui| {
-                                    ui.label("Type:");
-                                    if egui::ComboBox::from_id_salt("type")
-                                        .selected_text(parameter.type_.display_name())
-                                        .show_ui(ui, |ui| {
-                                            let mut changed = false;
-                                            for type_ in [
-                                                ParameterType::Grade0,
-                                                ParameterType::Grade1,
-                                                ParameterType::Grade2,
-                                                ParameterType::Grade3,
-                                                ParameterType::Multivector,
-                                            ] {
-                                                changed |= ui
-                                                    .selectable_value(
-                                                        &mut parameter.type_,
-                                                        type_,
-                                                        type_.display_name(),
-                                                    )
-                                                    .changed();
-                                            }
-                                            changed
-                                        })
-                                        .inner
-                                        .unwrap_or(false)
-                                    {
-                                        parameter.value = match parameter.type_ {
-                                            ParameterType::Grade0 => parameter.value.grade0(),
-                                            ParameterType::Grade1 => parameter.value.grade1(),
-                                            ParameterType::Grade2 => parameter.value.grade2(),
-                                            ParameterType::Grade3 => parameter.value.grade3(),
-                                            ParameterType::Multivector => parameter.value,
-                                        };
-                                        code_or_parameters_changed = true;
-                                    }
-                                });
+    // every statement is in exactly one level
+    Some(outcomes.into_iter().map(Option::unwrap).collect())
+}
 
-                                if ui.button("Normalise").clicked() {
-                                    parameter.value = parameter.value.normalized();
-                                }
+/// Error lists longer than this start collapsed, if that is turned on
+const ERRORS_COLLAPSE_THRESHOLD: usize = 3;
 
-                                let (grade0, grade1, grade2, grade3) = match parameter.type_ {
-                                    ParameterType::Grade0 => (true, false, false, false),
-                                    ParameterType::Grade1 => (false, true, false, false),
-                                    ParameterType::Grade2 => (false, false, true, false),
-                                    ParameterType::Grade3 => (false, false, false, true),
-                                    ParameterType::Multivector => (true, true, true, true),
-                                };
+/// Whether the error list should start expanded
+fn errors_start_open(count: usize, collapse_long_errors: bool) -> bool {
+    !collapse_long_errors || count <= ERRORS_COLLAPSE_THRESHOLD
+}
 
-                                code_or_parameters_changed |= edit_multivector(
-                                    ui,
-                                    &mut parameter.value,
-                                    grade0,
-                                    grade1,
-                                    grade2,
-                                    grade3,
-                                );
+/// Converts a location within a substring of the code that starts at `base` into a location in the whole code
+fn offset_location(base: Location, location: Location) -> Location {
+    Location {
+        position: base.position + location.position,
+        line: base.line + location.line - 1,
+        column: if location.line == 1 {
+            base.column + location.column - 1
+        } else {
+            location.column
+        },
+    }
+}
 
-                                delete = ui.button("Delete").clicked();
-                                code_or_parameters_changed |= delete;
-                            });
+/// Where the direction of an ideal point meets `border` when looking out from its center,
+/// `None` if the point is not ideal
+fn ideal_point_on_border(
+    camera: &Camera,
+    border: egui::Rect,
+    point: Multivector,
+) -> Option<egui::Pos2> {
+    if point.e12.abs() > 0.0001 {
+        return None;
+    }
+    let transform = camera.transform.normalized();
+    let relative = transform.reverse() * point * transform;
+    // screen y points down
+    let direction = egui::vec2(-relative.e02, -relative.e01);
+    if direction.length() == 0.0 {
+        return None;
+    }
+    let scale =
+        (border.width() * 0.5 / direction.x.abs()).min(border.height() * 0.5 / direction.y.abs());
+    Some(border.center() + direction * scale)
+}
 
-                        i += 1;
-                        !delete
-                    });
-                    ui.allocate_space(ui.available_size());
+/// How close in pixels a dragged point has to get to a displayed point to snap onto it
+const POINT_SNAP_DISTANCE: f32 = 10.0;
+
+/// The candidate whose screen position is closest to `position`, if it is within `threshold` pixels
+fn snap_target<T>(
+    position: egui::Pos2,
+    candidates: impl IntoIterator<Item = (T, egui::Pos2)>,
+    threshold: f32,
+) -> Option<(T, egui::Pos2)> {
+    candidates
+        .into_iter()
+        .map(|(candidate, screen_position)| {
+            let distance = screen_position.distance(position);
+            (candidate, screen_position, distance)
+        })
+        .filter(|&(_, _, distance)| distance <= threshold)
+        .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b))
+        .map(|(candidate, screen_position, _)| (candidate, screen_position))
+}
+
+/// `name` and every variable the code computes from it, directly or through other variables.
+/// Code that does not parse is treated as computing nothing
+fn dependent_names<'a>(code: &'a str, name: &'a str) -> HashSet<&'a str> {
+    let mut dependents = HashSet::from([name]);
+    for statement in parse(code).unwrap_or_default() {
+        match statement.kind {
+            AstStatementKind::Assignment {
+                name, ref value, ..
+            } => {
+                let mut reads_dependent = false;
+                value.for_each_name(&mut |read, _| {
+                    reads_dependent |= dependents.contains(read);
                 });
-            });
+                if reads_dependent {
+                    dependents.insert(name);
+                }
+            }
+        }
+    }
+    dependents
+}
 
-        egui::Window::new("Code")
-            .open(&mut self.code_window_open)
-            .scroll(true)
-            .show(ctx, |ui| {
-                if !self.errors.is_empty() {
-                    ui.heading("Errors:");
-                    for error in &self.errors {
-                        ui.label(egui::RichText::new(error).color(egui::Color32::RED));
+fn is_hidden(hidden_groups: &BTreeSet<String>, variable: &Variable) -> bool {
+    variable
+        .group
+        .as_ref()
+        .is_some_and(|group| hidden_groups.contains(group))
+}
+
+/// Draws the displayed variables and polygons as an svg the size of `rect`, points are drawn with their
+/// point style and lines are sampled as polylines so they also bend correctly in the curved flavours
+fn scene_to_svg(app: &App, rect: egui::Rect) -> String {
+    use std::fmt::Write;
+
+    const LINE_SAMPLES: usize = 256;
+
+    let pixels_per_unit = rect.height() / app.camera.view_height;
+    let point_radius = app.camera.point_radius * pixels_per_unit;
+    let line_width = app.camera.line_thickness * pixels_per_unit;
+    let to_svg = |position: egui::Pos2| position - rect.min;
+    let svg_color =
+        |color: egui::Color32| format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b());
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        rect.width(),
+        rect.height()
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        svg_color(app.theme.background())
+    )
+    .unwrap();
+
+    for polygon in &app.polygons {
+        let Some(positions) = polygon
+            .points
+            .iter()
+            .map(|name| {
+                let point = app.variables.get(name)?.value.grade2();
+                world_to_screen(&app.camera, rect, point).map(to_svg)
+            })
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        let points = positions
+            .iter()
+            .map(|position| format!("{},{}", position.x, position.y))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            svg,
+            r#"<polygon points="{points}" fill="{}" fill-opacity="{}"/>"#,
+            svg_color(color_to_egui(polygon.color)),
+            polygon.opacity
+        )
+        .unwrap();
+    }
+
+    let view_center = screen_to_world(&app.camera, rect, rect.center());
+    let mut displayed = app
+        .variables
+        .values()
+        .filter(|variable| !is_hidden(&app.hidden_groups, variable) && variable.value.is_finite())
+        .filter_map(|variable| Some((variable.value, variable.display.as_ref()?)))
+        .collect::<Vec<_>>();
+    displayed.sort_by(|(_, a), (_, b)| draw_order(a, b));
+
+    for (value, display) in displayed {
+        let color = svg_color(color_to_egui(display.color));
+        let scale = if app.camera.show_weight {
+            weight_scale(value)
+        } else {
+            1.0
+        };
+        let line_width = line_width * scale;
+
+        let line = value.grade1();
+        if line.sqr_magnitude() > 0.0001 {
+            // meet the line with lines through the view center at every angle, splitting the
+            // polyline wherever a sample goes off to infinity
+            let perpendicular = line.inner(view_center);
+            let mut polylines = vec![vec![]];
+            for i in 0..=LINE_SAMPLES {
+                let angle = (i as f32 / LINE_SAMPLES as f32 - 0.5) * std::f32::consts::PI;
+                let rotor = Multivector::exp(view_center * (angle * 0.5));
+                let point = line.wedge(rotor * perpendicular * rotor.reverse());
+                match world_to_screen(&app.camera, rect, point)
+                    .filter(|position| rect.expand(rect.size().max_elem()).contains(*position))
+                {
+                    Some(position) => polylines.last_mut().unwrap().push(to_svg(position)),
+                    None => polylines.push(vec![]),
+                }
+            }
+            for polyline in polylines.into_iter().filter(|polyline| polyline.len() >= 2) {
+                let points = polyline
+                    .iter()
+                    .map(|position| format!("{},{}", position.x, position.y))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                writeln!(
+                    svg,
+                    r#"<polyline points="{points}" fill="none" stroke="{color}" stroke-width="{line_width}"/>"#
+                )
+                .unwrap();
+            }
+        }
+
+        let point = value.grade2();
+        if point.sqr_magnitude() > 0.0001
+            && let Some(position) = world_to_screen(&app.camera, rect, point).map(to_svg)
+        {
+            let (x, y, r) = (position.x, position.y, point_radius * scale);
+            // the cross and square are aligned to the world axes like in the shader, so their
+            // corners are offset in the world, in multiples of the point radius, and then projected
+            let world_radius = app.camera.point_radius * scale;
+            let outline = |corners: &[(f32, f32)]| {
+                let (point_x, point_y) = point.point_coordinates()?;
+                corners
+                    .iter()
+                    .map(|&(dx, dy)| {
+                        let corner = Multivector::point(
+                            point_x + dx * world_radius,
+                            point_y + dy * world_radius,
+                        );
+                        let position = to_svg(world_to_screen(&app.camera, rect, corner)?);
+                        Some(format!("{},{}", position.x, position.y))
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .map(|points| points.join(" "))
+            };
+            match display.point_style {
+                PointStyle::Disc => {
+                    writeln!(svg, r#"<circle cx="{x}" cy="{y}" r="{r}" fill="{color}"/>"#).unwrap();
+                }
+                // the shader draws everything between 0.6 and 1 times the radius
+                PointStyle::Ring => writeln!(
+                    svg,
+                    r#"<circle cx="{x}" cy="{y}" r="{}" fill="none" stroke="{color}" stroke-width="{}"/>"#,
+                    r * 0.8,
+                    r * 0.4
+                )
+                .unwrap(),
+                // a plus with bars half the radius wide
+                PointStyle::Cross => {
+                    const BAR: f32 = 0.25;
+                    if let Some(points) = outline(&[
+                        (BAR, BAR),
+                        (1.0, BAR),
+                        (1.0, -BAR),
+                        (BAR, -BAR),
+                        (BAR, -1.0),
+                        (-BAR, -1.0),
+                        (-BAR, -BAR),
+                        (-1.0, -BAR),
+                        (-1.0, BAR),
+                        (-BAR, BAR),
+                        (-BAR, 1.0),
+                        (BAR, 1.0),
+                    ]) {
+                        writeln!(svg, r#"<polygon points="{points}" fill="{color}"/>"#).unwrap();
+                    }
+                }
+                PointStyle::Square => {
+                    if let Some(points) =
+                        outline(&[(1.0, 1.0), (1.0, -1.0), (-1.0, -1.0), (-1.0, 1.0)])
+                    {
+                        writeln!(svg, r#"<polygon points="{points}" fill="{color}"/>"#).unwrap();
                     }
                 }
-                code_or_parameters_changed |= ui
-                    .add(
-                        egui::TextEdit::multiline(&mut self.code)
-                            .id_salt("code")
-                            .code_editor()
-                            .desired_width(f32::INFINITY)
-                            .min_size(ui.available_size()),
-                    )
-                    .changed();
+            }
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Larger z orders and then larger layers are drawn on top, the same as the depth test in the shader,
+/// so sorting by this puts the objects that have to be drawn last at the end
+fn draw_order(a: &VariableDisplay, b: &VariableDisplay) -> std::cmp::Ordering {
+    a.z_order.cmp(&b.z_order).then(a.layer.total_cmp(&b.layer))
+}
+
+/// How much bigger an object is drawn when showing weights, the magnitude of its point part
+/// or of its line part if it has no point, clamped so that objects never vanish or fill the screen
+fn weight_scale(value: Multivector) -> f32 {
+    let point = value.grade2();
+    let magnitude = if point.sqr_magnitude().abs() > 0.0001 {
+        point.magnitude()
+    } else {
+        value.grade1().magnitude()
+    };
+    magnitude.clamp(0.1, 10.0)
+}
+
+fn label_anchor(value: Multivector, view_center: Multivector) -> Option<Multivector> {
+    let point = value.grade2();
+    if point.sqr_magnitude() > 0.0001 {
+        return Some(point);
+    }
+    let line = value.grade1();
+    if line.sqr_magnitude() > 0.0001 {
+        return Some(line.wedge(line.inner(view_center)));
+    }
+    None
+}
+
+/// Groups the names of values that would be drawn in the same place, only groups with more than one name are returned.
+/// Values are compared after normalizing so scaled copies and opposite orientations still count as coincident
+fn coincident_groups<'a>(
+    values: impl IntoIterator<Item = (&'a String, Multivector)>,
+) -> Vec<Vec<&'a String>> {
+    const EPSILON: f32 = 1e-4;
+    let mut groups: Vec<(Multivector, Vec<&'a String>)> = vec![];
+    for (name, value) in values {
+        let value = value.normalized();
+        match groups.iter_mut().find(|(representative, _)| {
+            representative.approx_eq(value, EPSILON) || representative.approx_eq(-value, EPSILON)
+        }) {
+            Some((_, names)) => names.push(name),
+            None => groups.push((value, vec![name])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, names)| names)
+        .filter(|names| names.len() > 1)
+        .collect()
+}
+
+/// Returns the angle between two lines, and the distance between any two points or lines
+fn measure(first: Multivector, second: Multivector) -> (Option<f32>, Option<f32>) {
+    let is_point = |value: Multivector| value.grade2().sqr_magnitude() > 0.0001;
+    let is_line = |value: Multivector| value.grade1().sqr_magnitude() > 0.0001;
+
+    if is_point(first) && is_point(second) {
+        let distance = first
+            .grade2()
+            .normalized()
+            .regressive(second.grade2().normalized())
+            .magnitude();
+        (None, Some(distance))
+    } else if is_line(first) && is_line(second) {
+        let (first, second) = (first.grade1().normalized(), second.grade1().normalized());
+        let cos = first.dot(second);
+        let angle = cos.abs().min(1.0).acos();
+        let distance = if first.wedge(second).e12.abs() < 0.0001 {
+            (first.e0 - cos.signum() * second.e0).abs()
+        } else {
+            0.0
+        };
+        (Some(angle), Some(distance))
+    } else if is_line(first) || is_line(second) {
+        let (line, point) = if is_line(first) {
+            (first.grade1(), second.grade2())
+        } else {
+            (second.grade1(), first.grade2())
+        };
+        let distance = line.normalized().regressive(point.normalized()).magnitude();
+        (None, Some(distance))
+    } else {
+        (None, None)
+    }
+}
+
+/// How many expressions the code can evaluate by default before it is stopped,
+/// which is a limit on its size since nothing is evaluated more than once
+const DEFAULT_EVALUATION_BUDGET: usize = 1_000_000;
+
+/// How far in from the edge of the view the ideal line is drawn, in pixels
+const IDEAL_LINE_INSET: f32 = 6.0;
+
+/// Components smaller than this are shown as zero when cleaning values
+const CLEAN_EPSILON: f32 = 0.000001;
+
+/// The colors used to show each grade of a product, indexed by grade
+const PRODUCT_GRADE_COLORS: [cgmath::Vector3<f32>; 4] = [
+    cgmath::Vector3 {
+        x: 1.0,
+        y: 1.0,
+        z: 1.0,
+    },
+    cgmath::Vector3 {
+        x: 0.2,
+        y: 0.8,
+        z: 1.0,
+    },
+    cgmath::Vector3 {
+        x: 1.0,
+        y: 0.5,
+        z: 0.1,
+    },
+    cgmath::Vector3 {
+        x: 0.8,
+        y: 0.3,
+        z: 1.0,
+    },
+];
+
+fn color_to_egui(color: cgmath::Vector3<f32>) -> egui::Color32 {
+    egui::Color32::from_rgb(
+        (color.x * 255.0) as u8,
+        (color.y * 255.0) as u8,
+        (color.z * 255.0) as u8,
+    )
+}
+
+/// The most points a single import will create, so a large file can't flood the scene
+const MAX_IMPORTED_POINTS: usize = 1000;
+
+/// Parses lines of `x,y` into points, blank lines are skipped and bad lines are reported by line number
+fn parse_points_csv(text: &str) -> (Vec<(f32, f32)>, Vec<String>) {
+    let mut points = vec![];
+    let mut errors = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if points.len() >= MAX_IMPORTED_POINTS {
+            errors.push(format!(
+                "Only the first {MAX_IMPORTED_POINTS} points were imported"
+            ));
+            break;
+        }
+
+        let line_number = index + 1;
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+        let [x, y] = fields[..] else {
+            errors.push(format!(
+                "Line {line_number}: expected 2 values but found {}",
+                fields.len()
+            ));
+            continue;
+        };
+        match (x.parse(), y.parse()) {
+            (Ok(x), Ok(y)) => points.push((x, y)),
+            (Err(error), _) | (_, Err(error)) => {
+                errors.push(format!("Line {line_number}: {error}"));
+            }
+        }
+    }
+    (points, errors)
+}
+
+fn select_variable(
+    ui: &mut egui::Ui,
+    id_salt: impl std::hash::Hash,
+    label: &str,
+    selected: &mut Option<String>,
+    names: &[&String],
+) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        egui::ComboBox::from_id_salt(id_salt)
+            .selected_text(selected.as_deref().unwrap_or(""))
+            .show_ui(ui, |ui| {
+                for &name in names {
+                    ui.selectable_value(selected, Some(name.clone()), name);
+                }
             });
+    });
+}
 
-        if code_or_parameters_changed {
-            self.update_code();
-        }
+fn cycle_index(current: Option<usize>, count: usize, backwards: bool) -> Option<usize> {
+    if count == 0 {
+        return None;
+    }
+    Some(match (current, backwards) {
+        (None, false) => 0,
+        (None, true) => count - 1,
+        (Some(index), false) => (index + 1) % count,
+        (Some(index), true) => (index + count - 1) % count,
+    })
+}
 
-        egui::Window::new("Variables")
-            .open(&mut self.variables_window_open)
-            .scroll([false, true])
-            .show(ctx, |ui| {
-                for (name, variable) in &mut self.variables {
-                    let color = variable.display.as_ref().map(|display| {
-                        egui::Color32::from_rgb(
-                            (display.color.x * 255.0) as u8,
-                            (display.color.y * 255.0) as u8,
-                            (display.color.z * 255.0) as u8,
-                        )
-                    });
-                    egui::CollapsingHeader::new(
-                        egui::RichText::new(name).color(color.unwrap_or(egui::Color32::WHITE)),
-                    )
-                    .show(ui, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.label("Display:");
-                            let mut display_enabled = variable.display.is_some();
-                            if ui.checkbox(&mut display_enabled, "").changed() {
-                                if display_enabled {
-                                    variable.display = Some(VariableDisplay {
-                                        color: cgmath::Vector3 {
-                                            x: 1.0,
-                                            y: 1.0,
-                                            z: 1.0,
-                                        },
-                                        layer: 0.05,
-                                    });
-                                } else {
-                                    variable.display = None;
-                                }
-                            }
-                        });
+#[allow(clippy::too_many_arguments)]
+fn edit_variable(
+    ui: &mut egui::Ui,
+    name: &str,
+    variable: &mut Variable,
+    expanded: &mut BTreeSet<String>,
+    pinned: bool,
+    angle_unit: AngleUnit,
+    scientific_notation: bool,
+    clean_values: bool,
+    display_defaults: DisplayDefaults,
+) -> bool {
+    let color = variable
+        .display
+        .as_ref()
+        .map(|display| color_to_egui(display.color));
+    let mut toggled_pin = false;
+    let header = egui::CollapsingHeader::new(
+        egui::RichText::new(name).color(color.unwrap_or_else(|| ui.visuals().text_color())),
+    )
+    .default_open(expanded.contains(name))
+    .show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Pinned:");
+            let mut pinned = pinned;
+            toggled_pin = ui.checkbox(&mut pinned, "").changed();
+        });
 
-                        if let Some(display) = &mut variable.display {
-                            ui.horizontal(|ui| {
-                                ui.label("Color:");
-                                ui.color_edit_button_rgb(display.color.as_mut());
-                            });
+        ui.horizontal(|ui| {
+            ui.label("Group:");
+            let mut grouped = variable.group.is_some();
+            if ui.checkbox(&mut grouped, "").changed() {
+                variable.group = grouped.then(|| "group".to_string());
+            }
+            if let Some(group) = &mut variable.group {
+                ui.text_edit_singleline(group);
+            }
+        });
 
-                            ui.horizontal(|ui| {
-                                ui.label("Layer");
-                                ui.add(egui::Slider::new(&mut display.layer, 0.0..=1.0));
-                            });
-                        }
+        ui.horizontal(|ui| {
+            ui.label("Frozen:");
+            let mut frozen = variable.frozen.is_some();
+            if ui
+                .checkbox(&mut frozen, "")
+                .on_hover_text("Keeps showing the current value while the code changes it")
+                .changed()
+            {
+                variable.frozen = frozen.then_some(variable.value);
+            }
+        });
 
-                        ui.collapsing("Value", |ui| {
-                            ui.add_enabled_ui(false, |ui| {
-                                edit_multivector(ui, &mut variable.value, true, true, true, true);
-                            });
-                        });
-                    });
+        ui.horizontal(|ui| {
+            ui.label("Display:");
+            let mut display_enabled = variable.display.is_some();
+            if ui.checkbox(&mut display_enabled, "").changed() {
+                if display_enabled {
+                    variable.display = Some(display_defaults.display());
+                } else {
+                    variable.display = None;
                 }
-            });
+            }
+        });
 
-        if !ctx.wants_keyboard_input() {
-            ctx.input(|i| {
-                let mut move_direction = cgmath::Vector2 { x: 0.0, y: 0.0 };
-                move_direction.y += i.key_down(egui::Key::W) as u8 as f32;
-                move_direction.y -= i.key_down(egui::Key::S) as u8 as f32;
-                move_direction.x -= i.key_down(egui::Key::A) as u8 as f32;
-                move_direction.x += i.key_down(egui::Key::D) as u8 as f32;
+        if let Some(display) = &mut variable.display {
+            ui.horizontal(|ui| {
+                ui.label("Color:");
+                ui.color_edit_button_rgb(display.color.as_mut());
+            });
 
-                let inf_point = Multivector {
-                    e1: move_direction.x,
-                    e2: move_direction.y,
-                    ..Multivector::ZERO
-                }
-                .wedge(Multivector {
-                    e0: 1.0,
-                    ..Multivector::ZERO
-                });
+            ui.horizontal(|ui| {
+                ui.label("Layer");
+                ui.add(egui::Slider::new(&mut display.layer, 0.0..=1.0));
+            });
 
-                let motor = Multivector::exp(
-                    inf_point.normalized()
-                        * (self.camera.move_speed * self.camera.view_height * dt * 0.5),
-                );
+            ui.horizontal(|ui| {
+                ui.label("Z Order:");
+                ui.add(egui::DragValue::new(&mut display.z_order))
+                    .on_hover_text(
+                        "Larger z orders are drawn on top regardless of layer, \
+                     the layer only decides between objects with the same z order",
+                    );
+            });
 
-                self.camera.transform = self.camera.transform * motor;
+            ui.horizontal(|ui| {
+                ui.label("Point Style:");
+                egui::ComboBox::from_id_salt("point_style")
+                    .selected_text(display.point_style.display_name())
+                    .show_ui(ui, |ui| {
+                        for point_style in [
+                            PointStyle::Disc,
+                            PointStyle::Ring,
+                            PointStyle::Cross,
+                            PointStyle::Square,
+                        ] {
+                            ui.selectable_value(
+                                &mut display.point_style,
+                                point_style,
+                                point_style.display_name(),
+                            );
+                        }
+                    });
+            });
 
-                self.camera.view_height += i.key_down(egui::Key::Q) as u8 as f32
-                    * (self.camera.zoom_speed * self.camera.view_height * dt);
-                self.camera.view_height -= i.key_down(egui::Key::E) as u8 as f32
-                    * (self.camera.zoom_speed * self.camera.view_height * dt);
+            ui.horizontal(|ui| {
+                ui.label("Label:");
+                let mut label_enabled = display.label.is_some();
+                if ui.checkbox(&mut label_enabled, "").changed() {
+                    display.label = label_enabled.then(|| name.to_string());
+                }
+                if let Some(label) = &mut display.label {
+                    ui.text_edit_singleline(label);
+                }
             });
         }
 
-        egui::CentralPanel::default()
-            .frame(egui::Frame::NONE.fill(egui::Color32::from_rgb(50, 50, 50)))
-            .show(ctx, |ui| {
-                let (rect, _response) =
-                    ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
-                let aspect = rect.width() / rect.height();
-
-                let mut objects = vec![];
+        let value = if clean_values {
+            variable.value.approx_zero(CLEAN_EPSILON)
+        } else {
+            variable.value
+        };
+        if variable.display.is_some() && !value.is_finite() {
+            ui.label(
+                egui::RichText::new("Value is not finite, so it is not rendered")
+                    .color(egui::Color32::YELLOW),
+            );
+        }
 
-                for variable in self.variables.values() {
-                    if let Some(display) = &variable.display {
-                        objects.push(GpuObject {
-                            value: variable.value,
-                            color: display.color,
-                            layer: display.layer,
-                        });
-                    }
-                }
+        if value.e0 == 0.0
+            && value.e1 == 0.0
+            && value.e2 == 0.0
+            && value.e012 == 0.0
+            && value.e12 != 0.0
+        {
+            ui.label(format!(
+                "Rotation Angle: {}",
+                angle_unit.format(value.rotation_angle())
+            ));
+        }
 
-                self.camera.view_height = self.camera.view_height.max(0.1);
-                ui.painter()
-                    .add(eframe::egui_wgpu::Callback::new_paint_callback(
-                        rect,
-                        RenderData {
-                            camera: GpuCamera {
-                                transform: self.camera.transform,
-                                vertical_height: self.camera.view_height,
-                                aspect,
-                                line_thickness: self.camera.line_thickness,
-                                point_radius: self.camera.point_radius,
-                                flavour: GA_FLAVOUR.load(Ordering::Relaxed) as u32,
-                            },
-                            objects,
-                        },
-                    ));
+        ui.collapsing("Value", |ui| {
+            ui.add_enabled_ui(false, |ui| {
+                let mut value = value;
+                edit_multivector(ui, &mut value, true, true, true, true, scientific_notation);
             });
+        })
+        .header_response
+        .on_hover_text(format!(
+            "Largest Component: {}\nSmallest Component: {}",
+            format_readout(value.max_component_abs(), scientific_notation),
+            format_readout(value.min_component_abs(), scientific_notation),
+        ));
+    });
+    record_expanded(expanded, name, !header.fully_closed());
+    toggled_pin
+}
 
-        ctx.request_repaint();
+/// The variable that always holds the animation time
+const TIME_VARIABLE: &str = "t";
+
+/// Why a parameter or driver called `name` could not be used by scripts, if it can't
+fn name_problem(name: &str) -> Option<&'static str> {
+    if name == TIME_VARIABLE {
+        Some("This name is taken by the animation time, which would overwrite this value")
+    } else if !is_identifier(name) {
+        Some(
+            "Scripts cannot use this name, names start with a letter or _ \
+             and only contain letters, digits, and _",
+        )
+    } else {
+        None
     }
+}
 
-    fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        storage.set_string("App", ron::to_string(self).unwrap());
-        storage.set_string(
-            "Flavour",
-            ron::to_string(&GA_FLAVOUR.load(Ordering::Relaxed)).unwrap(),
+/// A text edit for the name of a parameter or driver, outlined with a button to fix it when scripts
+/// could not use it. Returns whether the name changed
+fn edit_name(ui: &mut egui::Ui, name: &mut String) -> bool {
+    let response = ui.text_edit_singleline(name);
+    let mut changed = response.changed();
+    if let Some(problem) = name_problem(name) {
+        ui.painter().rect_stroke(
+            response.rect,
+            2.0,
+            egui::Stroke::new(1.0, egui::Color32::RED),
+            egui::StrokeKind::Outside,
         );
+        response.on_hover_text(problem);
+        if ui.button("Sanitize").clicked() {
+            *name = sanitize_identifier(name);
+            if name == TIME_VARIABLE {
+                name.push('_');
+            }
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Adds or removes `name` from the set of expanded headers
+fn record_expanded(expanded: &mut BTreeSet<String>, name: &str, open: bool) {
+    if open {
+        if !expanded.contains(name) {
+            expanded.insert(name.to_string());
+        }
+    } else {
+        expanded.remove(name);
     }
 }
 
@@ -648,13 +5212,16 @@ fn edit_multivector(
     grade1: bool,
     grade2: bool,
     grade3: bool,
+    scientific_notation: bool,
 ) -> bool {
+    // large values would take forever to drag at a fixed speed, so it scales with the largest component
+    let speed = (value.max_component_abs() * 0.01).max(0.1);
     let mut changed = false;
     if grade0 {
         ui.horizontal(|ui| {
             ui.label("Scalar:");
             changed |= ui
-                .add(egui::DragValue::new(&mut value.s).speed(0.1))
+                .add(component_drag_value(&mut value.s, scientific_notation).speed(speed))
                 .changed();
         });
     }
@@ -662,19 +5229,19 @@ fn edit_multivector(
         ui.horizontal(|ui| {
             ui.label("e0:");
             changed |= ui
-                .add(egui::DragValue::new(&mut value.e0).speed(0.1))
+                .add(component_drag_value(&mut value.e0, scientific_notation).speed(speed))
                 .changed();
         });
         ui.horizontal(|ui| {
             ui.label("e1:");
             changed |= ui
-                .add(egui::DragValue::new(&mut value.e1).speed(0.1))
+                .add(component_drag_value(&mut value.e1, scientific_notation).speed(speed))
                 .changed();
         });
         ui.horizontal(|ui| {
             ui.label("e2:");
             changed |= ui
-                .add(egui::DragValue::new(&mut value.e2).speed(0.1))
+                .add(component_drag_value(&mut value.e2, scientific_notation).speed(speed))
                 .changed();
         });
     }
@@ -682,19 +5249,19 @@ fn edit_multivector(
         ui.horizontal(|ui| {
             ui.label("e01:");
             changed |= ui
-                .add(egui::DragValue::new(&mut value.e01).speed(0.1))
+                .add(component_drag_value(&mut value.e01, scientific_notation).speed(speed))
                 .changed();
         });
         ui.horizontal(|ui| {
             ui.label("e02:");
             changed |= ui
-                .add(egui::DragValue::new(&mut value.e02).speed(0.1))
+                .add(component_drag_value(&mut value.e02, scientific_notation).speed(speed))
                 .changed();
         });
         ui.horizontal(|ui| {
             ui.label("e12:");
             changed |= ui
-                .add(egui::DragValue::new(&mut value.e12).speed(0.1))
+                .add(component_drag_value(&mut value.e12, scientific_notation).speed(speed))
                 .changed();
         });
     }
@@ -702,13 +5269,162 @@ fn edit_multivector(
         ui.horizontal(|ui| {
             ui.label("e012:");
             changed |= ui
-                .add(egui::DragValue::new(&mut value.e012).speed(0.1))
+                .add(component_drag_value(&mut value.e012, scientific_notation).speed(speed))
                 .changed();
         });
     }
     changed
 }
 
+/// Edits the `e0` component of a grade 1 value directly, and `e1` and `e2` as a magnitude and angle
+fn edit_polar(
+    ui: &mut egui::Ui,
+    value: &mut Multivector,
+    angle_unit: AngleUnit,
+    scientific_notation: bool,
+) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label("e0:");
+        changed |= ui
+            .add(component_drag_value(&mut value.e0, scientific_notation))
+            .changed();
+    });
+
+    let mut magnitude = value.e1.hypot(value.e2);
+    let mut angle = angle_unit.from_radians(value.e2.atan2(value.e1));
+    let mut polar_changed = false;
+    ui.horizontal(|ui| {
+        ui.label("Magnitude:");
+        polar_changed |= ui
+            .add(component_drag_value(&mut magnitude, scientific_notation))
+            .changed();
+    });
+    ui.horizontal(|ui| {
+        ui.label("Angle:");
+        polar_changed |= ui
+            .add(
+                component_drag_value(&mut angle, scientific_notation).suffix(match angle_unit {
+                    AngleUnit::Radians => " rad",
+                    AngleUnit::Degrees => "°",
+                }),
+            )
+            .changed();
+    });
+    if polar_changed {
+        let (sin, cos) = angle_unit.to_radians(angle).sin_cos();
+        value.e1 = magnitude * cos;
+        value.e2 = magnitude * sin;
+    }
+    changed || polar_changed
+}
+
+/// Typing into a component accepts any expression with a scalar result, like `pi/2` or `1/3`,
+/// anything else is rejected and the field keeps its old value
+fn component_drag_value(value: &mut f32, scientific_notation: bool) -> egui::DragValue<'_> {
+    let drag_value = egui::DragValue::new(value)
+        .speed(0.1)
+        .custom_parser(parse_scalar_expression);
+    if scientific_notation {
+        drag_value.custom_formatter(|value, decimals| {
+            if is_extreme_magnitude(value) {
+                format!("{value:.3e}")
+            } else {
+                egui::emath::format_with_decimals_in_range(value, decimals)
+            }
+        })
+    } else {
+        drag_value
+    }
+}
+
+fn parse_scalar_expression(text: &str) -> Option<f64> {
+    let value = parse_multivector_expression(text)?;
+    value.is_scalar().then_some(value.scalar() as f64)
+}
+
+/// Evaluates `text` as an expression that can use the constants `pi` and `tau` and the basis blades,
+/// returns `None` if it is invalid or not finite
+fn parse_multivector_expression(text: &str) -> Option<Multivector> {
+    let expression = parse_expression(text).ok()?;
+    let constants = [("pi", std::f32::consts::PI), ("tau", std::f32::consts::TAU)]
+        .into_iter()
+        .map(|(name, value)| {
+            (
+                name,
+                Multivector {
+                    s: value,
+                    ..Multivector::ZERO
+                },
+            )
+        })
+        .chain(
+            Multivector::ZERO
+                .components()
+                .into_iter()
+                .filter_map(|(name, _)| Some((name, Multivector::basis_blade(name)?))),
+        )
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                Variable {
+                    value,
+                    frozen: None,
+                    group: None,
+                    display: None,
+                },
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    let value = evaluate_expression(
+        &expression,
+        &constants,
+        AngleUnit::Radians,
+        true,
+        &mut vec![],
+        &mut { DEFAULT_EVALUATION_BUDGET },
+    )
+    .ok()?;
+    value.is_finite().then_some(value)
+}
+
+/// How far the squared magnitude of a transform can be from 1 before it is flagged as scaling
+const UNIT_TOLERANCE: f32 = 0.001;
+
+/// Whether the sandwich of `transform` would also scale what it is applied to, since its magnitude is not 1
+fn is_scaled(transform: Multivector) -> bool {
+    (transform.sqr_magnitude() - 1.0).abs() > UNIT_TOLERANCE
+}
+
+/// Shows the squared magnitude of `transform`, with a warning if it is not 1
+fn scale_readout(ui: &mut egui::Ui, transform: Multivector, scientific_notation: bool) {
+    ui.horizontal(|ui| {
+        ui.label(format!(
+            "Squared Magnitude: {}",
+            format_readout(transform.sqr_magnitude(), scientific_notation)
+        ));
+        if is_scaled(transform) {
+            ui.label(egui::RichText::new("⚠").color(egui::Color32::YELLOW))
+                .on_hover_text("This is not 1, so applying the transform also scales, normalize it to fix this");
+        }
+    });
+}
+
+fn format_readout(value: f32, scientific_notation: bool) -> String {
+    if scientific_notation && is_extreme_magnitude(value as f64) {
+        format!("{value:.3e}")
+    } else {
+        // values that round to zero would otherwise keep their sign and show as `-0.000`
+        let value = if value.abs() < 0.0005 { 0.0 } else { value };
+        format!("{value:.3}")
+    }
+}
+
+/// Values this large or small are unreadable without scientific notation
+fn is_extreme_magnitude(value: f64) -> bool {
+    value != 0.0 && !(0.001..100000.0).contains(&value.abs())
+}
+
 fn main() -> eframe::Result<()> {
     eframe::run_native(
         "Geometric Algebra Sandbox",
@@ -724,3 +5440,486 @@ fn main() -> eframe::Result<()> {
         Box::new(|cc| Ok(Box::new(App::new(cc)))),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> ScriptSettings {
+        ScriptSettings {
+            angle_unit: AngleUnit::Radians,
+            strict_scalars: false,
+            budget: DEFAULT_EVALUATION_BUDGET,
+        }
+    }
+
+    fn basis_scope() -> BTreeMap<String, Variable> {
+        ["e0", "e1", "e2"]
+            .into_iter()
+            .map(|name| {
+                (
+                    name.to_string(),
+                    Variable {
+                        value: Multivector::basis_blade(name).unwrap(),
+                        frozen: None,
+                        group: None,
+                        display: None,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn statements_sharing_a_line_evaluate_in_order() {
+        let statements = parse("a = e1; b = missing; c = a * 2;").unwrap();
+        let mut scope = basis_scope();
+        let mut assigned = HashSet::from(["e0", "e1", "e2"]);
+        let outcomes = evaluate_statements(&statements, &mut scope, &mut assigned, settings());
+
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(
+            outcomes[0].result,
+            StatementResult::Value(Multivector::basis_blade("e1").unwrap())
+        );
+        let StatementResult::Error(error) = &outcomes[1].result else {
+            panic!("{:?} is not an error", outcomes[1].result);
+        };
+        assert_eq!((error.location.line, error.location.column), (1, 13));
+        assert_eq!(
+            outcomes[2].result,
+            StatementResult::Value(Multivector::basis_blade("e1").unwrap() * 2.0)
+        );
+        assert_eq!(
+            scope["c"].value,
+            Multivector::basis_blade("e1").unwrap() * 2.0
+        );
+        assert!(!assigned.contains("b"));
+    }
+
+    #[test]
+    fn parallel_evaluation_matches_serial() {
+        // forward references, failures, statements skipped because of them and warnings,
+        // with enough independent statements in the first level to run on several threads
+        let mut source = String::from(
+            "early = late * 2; late = e1; failed = missing; skipped = failed + e0; warned = sin(e1);\n",
+        );
+        for index in 0..MIN_PARALLEL_STATEMENTS * 2 {
+            source += &format!("v{index} = e0 * {index} + late ^ e2;\n");
+        }
+        let statements = parse(&source).unwrap();
+
+        let mut serial_scope = basis_scope();
+        let mut serial_assigned = HashSet::from(["e0", "e1", "e2"]);
+        let serial = evaluate_statements(
+            &statements,
+            &mut serial_scope,
+            &mut serial_assigned,
+            settings(),
+        );
+
+        let mut parallel_scope = basis_scope();
+        let mut parallel_assigned = HashSet::from(["e0", "e1", "e2"]);
+        let parallel = evaluate_in_parallel(
+            &statements,
+            &mut parallel_scope,
+            &mut parallel_assigned,
+            settings(),
+        )
+        .unwrap();
+
+        assert_eq!(parallel, serial);
+        assert!(matches!(serial[0].result, StatementResult::Error(_)));
+        assert!(matches!(serial[2].result, StatementResult::Error(_)));
+        assert_eq!(serial[3].result, StatementResult::Skipped);
+        assert_eq!(serial[4].warnings.len(), 1);
+        let values = |scope: &BTreeMap<String, Variable>| {
+            scope
+                .iter()
+                .map(|(name, variable)| (name.clone(), variable.value))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values(&parallel_scope), values(&serial_scope));
+        assert_eq!(parallel_assigned, serial_assigned);
+    }
+
+    #[test]
+    fn follow_motor_moves_towards_target() {
+        let start = Multivector::translator(0.0, 0.0);
+        let target = Multivector::translator(2.0, 1.0) * Multivector::rotor(1.0);
+
+        // no smoothing jumps straight to the target
+        assert_eq!(follow_motor(start, target, 0.0, 0.1), target);
+
+        let mut transform = start;
+        for _ in 0..1000 {
+            transform = follow_motor(transform, target, 0.2, 0.01);
+        }
+        assert!(transform.approx_eq(target, 1e-4), "{transform} != {target}");
+
+        // `-target` is the same motor, so following it ends up at the same place without turning around
+        let mut transform = start;
+        let first_step = follow_motor(transform, -target, 0.2, 0.01);
+        assert!(first_step.s > 0.0);
+        for _ in 0..1000 {
+            transform = follow_motor(transform, -target, 0.2, 0.01);
+        }
+        assert!(transform.approx_eq(target, 1e-4), "{transform} != {target}");
+    }
+
+    #[test]
+    fn svg_export_draws_points_and_lines() {
+        let mut app = App {
+            variables: BTreeMap::new(),
+            polygons: vec![],
+            ..App::default()
+        };
+        for (name, value) in [
+            ("point", Multivector::point(0.5, 0.5)),
+            ("line", Multivector::basis_blade("e2").unwrap()),
+        ] {
+            app.variables.insert(
+                name.into(),
+                Variable {
+                    value,
+                    frozen: None,
+                    display: Some(DisplayDefaults::default().display()),
+                    group: None,
+                },
+            );
+        }
+        let svg = scene_to_svg(
+            &app,
+            egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(200.0, 100.0)),
+        );
+
+        assert_eq!(svg.matches("<circle").count(), 1, "{svg}");
+        assert!(svg.contains("<polyline"), "{svg}");
+    }
+
+    #[test]
+    fn changes_in_one_frame_evaluate_once() {
+        let mut app = App {
+            code: "sum = e1 + e2;".into(),
+            ..App::default()
+        };
+        let mut pending_evaluation = PendingEvaluation::default();
+        let mut evaluations = 0;
+
+        app.parameters[1].value = app.parameters[1].value * 2.0;
+        pending_evaluation |= true;
+        app.parameters[2].value = app.parameters[2].value * 3.0;
+        pending_evaluation |= true;
+        pending_evaluation |= false;
+        pending_evaluation.run(|| {
+            evaluations += 1;
+            app.update_code();
+        });
+        pending_evaluation.run(|| evaluations += 1);
+
+        assert_eq!(evaluations, 1);
+        assert_eq!(
+            app.variables["sum"].value,
+            Multivector {
+                e1: 2.0,
+                e2: 3.0,
+                ..Multivector::ZERO
+            }
+        );
+    }
+
+    #[test]
+    fn changes_compare_each_component() {
+        let mut app = App {
+            code: "a = e1;".into(),
+            ..App::default()
+        };
+        app.update_code();
+        let snapshot = SavedSnapshot::new(&app);
+        assert_eq!(
+            snapshot.changes(&app.parameters, &app.variables, &app.code),
+            Vec::<String>::new()
+        );
+
+        app.parameters[1].value.e1 = 2.0;
+        app.parameters.remove(0);
+        app.code = "b = e2;".into();
+        app.update_code();
+        assert_eq!(
+            snapshot.changes(&app.parameters, &app.variables, &app.code),
+            [
+                "Removed parameter e0",
+                "e1.e1: 1 → 2",
+                "Added variable b",
+                "Removed variable a",
+                "Removed variable e0",
+                "Code changed",
+            ]
+        );
+    }
+
+    #[test]
+    fn axis_lines_meet_at_origin() {
+        let x_axis = Multivector::basis_blade("e2").unwrap();
+        let y_axis = Multivector::basis_blade("e1").unwrap();
+        let meet = x_axis.wedge(y_axis);
+        assert_eq!(meet_name(meet), "intersection");
+        assert_eq!(meet.point_coordinates(), Some((0.0, 0.0)));
+
+        let shifted_x_axis = x_axis + Multivector::basis_blade("e0").unwrap();
+        assert_eq!(meet_name(x_axis.wedge(shifted_x_axis)), "direction");
+    }
+
+    #[test]
+    fn cycle_index_wraps_around() {
+        assert_eq!(cycle_index(None, 3, false), Some(0));
+        assert_eq!(cycle_index(None, 3, true), Some(2));
+        assert_eq!(cycle_index(Some(1), 3, false), Some(2));
+        assert_eq!(cycle_index(Some(2), 3, false), Some(0));
+        assert_eq!(cycle_index(Some(0), 3, true), Some(2));
+        assert_eq!(cycle_index(Some(0), 0, false), None);
+    }
+
+    #[test]
+    fn animation_phase_per_mode() {
+        let phase = |mode, time| {
+            Animation {
+                time,
+                duration: 2.0,
+                mode,
+                ..Animation::default()
+            }
+            .phase()
+        };
+        assert_eq!(phase(PlaybackMode::Loop, 5.0), 1.0);
+        assert_eq!(phase(PlaybackMode::Loop, -0.5), 1.5);
+        assert_eq!(phase(PlaybackMode::PingPong, 1.5), 1.5);
+        assert_eq!(phase(PlaybackMode::PingPong, 3.0), 1.0);
+        assert_eq!(phase(PlaybackMode::PingPong, 5.0), 1.0);
+        assert_eq!(phase(PlaybackMode::Once, 5.0), 2.0);
+        assert_eq!(phase(PlaybackMode::Once, -1.0), 0.0);
+
+        let unbounded = Animation {
+            time: 7.0,
+            duration: 0.0,
+            ..Animation::default()
+        };
+        assert_eq!(unbounded.phase(), 7.0);
+    }
+
+    #[test]
+    fn readouts_round_to_three_places() {
+        assert_eq!(format_readout(1.23456, false), "1.235");
+        assert_eq!(format_readout(-0.0001, false), "0.000");
+        assert_eq!(format_readout(123456.0, false), "123456.000");
+        assert_eq!(format_readout(123456.0, true), "1.235e5");
+        assert_eq!(format_readout(0.0001, true), "1.000e-4");
+        // only extreme values switch to scientific notation
+        assert_eq!(format_readout(2.5, true), "2.500");
+        assert_eq!(format_readout(0.0, true), "0.000");
+    }
+
+    #[test]
+    fn perpendicular_lines_measure_a_right_angle() {
+        let [e0, e1, e2] = ["e0", "e1", "e2"].map(|name| Multivector::basis_blade(name).unwrap());
+        let (angle, distance) = measure(e1, (e2 + e0) * 3.0);
+        assert!((angle.unwrap() - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+        assert_eq!(distance, Some(0.0));
+
+        // parallel lines have no angle between them and are a fixed distance apart
+        let (angle, distance) = measure(e1, e1 - e0 * 2.0);
+        assert_eq!(angle, Some(0.0));
+        assert_eq!(distance, Some(2.0));
+    }
+
+    #[test]
+    fn points_csv_reports_bad_lines() {
+        let (points, errors) = parse_points_csv("1, 2\n\n-3.5,4\n1,2,3\nx,1\n");
+        assert_eq!(points, [(1.0, 2.0), (-3.5, 4.0)]);
+        assert_eq!(
+            errors,
+            [
+                "Line 4: expected 2 values but found 3",
+                "Line 5: invalid float literal",
+            ]
+        );
+
+        let many = "0,0\n".repeat(MAX_IMPORTED_POINTS + 1);
+        let (points, errors) = parse_points_csv(&many);
+        assert_eq!(points.len(), MAX_IMPORTED_POINTS);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn toggling_line_comments() {
+        let code = "a = e1;\n\n  b = e2;\nc = e0;\n";
+        // blank lines in the selection are left alone, and indentation is kept
+        let commented = toggle_line_comments(code, 2..12);
+        assert_eq!(commented, "// a = e1;\n\n//   b = e2;\nc = e0;\n");
+        assert_eq!(toggle_line_comments(&commented, 0..16), code);
+
+        // a selection that ends at the start of a line stops before it, and an empty one is its line
+        assert_eq!(
+            toggle_line_comments(code, 0..8),
+            "// a = e1;\n\n  b = e2;\nc = e0;\n"
+        );
+        assert_eq!(
+            toggle_line_comments(code, 21..21),
+            "a = e1;\n\n  b = e2;\n// c = e0;\n"
+        );
+
+        // lines are only uncommented once every line is a comment
+        assert_eq!(
+            toggle_line_comments("// a = e1;\nb = e2;", 0..12),
+            "// // a = e1;\n// b = e2;"
+        );
+        assert_eq!(
+            toggle_line_comments("  //a = e1;\n// b = e2;", 0..13),
+            "  a = e1;\nb = e2;"
+        );
+    }
+
+    #[test]
+    fn ideal_points_sit_on_the_border() {
+        let camera = Camera::default();
+        let border = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(200.0, 100.0));
+        let direction = |x: f32, y: f32| Multivector {
+            e01: y,
+            e02: -x,
+            ..Multivector::ZERO
+        };
+        let on_border = |point| ideal_point_on_border(&camera, border, point);
+        assert_eq!(
+            on_border(direction(1.0, 0.0)),
+            Some(egui::pos2(200.0, 50.0))
+        );
+        assert_eq!(on_border(direction(0.0, 1.0)), Some(egui::pos2(100.0, 0.0)));
+        assert_eq!(
+            on_border(direction(-2.0, -2.0)),
+            Some(egui::pos2(50.0, 100.0))
+        );
+        assert_eq!(on_border(Multivector::point(1.0, 0.0)), None);
+    }
+
+    #[test]
+    fn snap_angle_rounds_to_increment() {
+        let increment = std::f32::consts::FRAC_PI_4;
+        assert_eq!(snap_angle(0.7, increment), increment);
+        assert_eq!(snap_angle(-1.7, increment), -2.0 * increment);
+        assert_eq!(snap_angle(0.3, increment), 0.0);
+        // no increment turns snapping off
+        assert_eq!(snap_angle(0.7, 0.0), 0.7);
+    }
+
+    #[test]
+    fn preview_statement_under_cursor() {
+        let scope = basis_scope();
+        let preview = |code: &str, cursor| preview_statement(code, cursor, &scope, settings());
+
+        let code = "a = e1;\nb = e1 + e2\n// just a comment";
+        assert_eq!(preview(code, 2), Some(Ok("a = e1".into())));
+        // the cursor just after a semicolon is still in its statement, and the last one needs no semicolon
+        assert_eq!(preview(code, 7), Some(Ok("a = e1".into())));
+        assert_eq!(preview(code, 12), Some(Ok("b = e1 + e2".into())));
+        assert_eq!(preview("e1 * 2", 0), Some(Ok("2*e1".into())));
+        assert_eq!(preview("a = e1;\n// nothing", 12), None);
+        assert_eq!(
+            preview("a = e1;\nb = missing;", 12),
+            Some(Err("2:5: Unknown variable 'missing'".into()))
+        );
+    }
+
+    #[test]
+    fn scaled_and_negated_copies_are_coincident() {
+        let names = ["p", "scaled", "negated", "other", "line", "line_copy"].map(String::from);
+        let point = Multivector::point(1.0, 2.0);
+        let line = Multivector::basis_blade("e1").unwrap();
+        let values = [
+            point,
+            point * 3.0,
+            -point,
+            Multivector::point(2.0, 1.0),
+            line,
+            line * -0.5,
+        ];
+        assert_eq!(
+            coincident_groups(names.iter().zip(values)),
+            [
+                vec![&names[0], &names[1], &names[2]],
+                vec![&names[4], &names[5]],
+            ]
+        );
+    }
+
+    #[test]
+    fn long_error_lists_start_collapsed() {
+        assert!(errors_start_open(ERRORS_COLLAPSE_THRESHOLD, true));
+        assert!(!errors_start_open(ERRORS_COLLAPSE_THRESHOLD + 1, true));
+        assert!(errors_start_open(ERRORS_COLLAPSE_THRESHOLD + 1, false));
+    }
+
+    #[test]
+    fn snap_target_picks_closest_within_threshold() {
+        let candidates = [
+            ("far", egui::pos2(30.0, 0.0)),
+            ("near", egui::pos2(3.0, 4.0)),
+            ("nearer", egui::pos2(0.0, 2.0)),
+        ];
+        assert_eq!(
+            snap_target(egui::Pos2::ZERO, candidates, POINT_SNAP_DISTANCE),
+            Some(("nearer", egui::pos2(0.0, 2.0)))
+        );
+        assert_eq!(snap_target(egui::Pos2::ZERO, candidates, 1.0), None);
+        assert_eq!(
+            snap_target(egui::pos2(100.0, 0.0), candidates, POINT_SNAP_DISTANCE),
+            None
+        );
+    }
+
+    #[test]
+    fn forward_references_are_used_before_assignment() {
+        let statements = parse("a = b;\nb = e1;\nc = missing;\nd = c;").unwrap();
+        let mut scope = basis_scope();
+        let mut assigned = HashSet::from(["e0", "e1", "e2"]);
+        let outcomes = evaluate_statements(&statements, &mut scope, &mut assigned, settings());
+
+        let messages = outcomes
+            .iter()
+            .map(|outcome| match &outcome.result {
+                StatementResult::Error(error) => Some(error.message.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                Some("'b' used before assignment on line 2"),
+                None,
+                Some("Unknown variable 'missing'"),
+                None,
+            ]
+        );
+        // `d` reads `c`, which already reported its own error
+        assert_eq!(outcomes[3].result, StatementResult::Skipped);
+    }
+
+    #[test]
+    fn draw_order_sorts_by_z_order_then_layer() {
+        let display = |z_order, layer| VariableDisplay {
+            z_order,
+            layer,
+            ..DisplayDefaults::default().display()
+        };
+        let mut displays = [
+            display(1, 0.0),
+            display(0, 0.5),
+            display(-1, 0.9),
+            display(0, 0.1),
+        ];
+        displays.sort_by(draw_order);
+        assert_eq!(
+            displays.map(|display| (display.z_order, display.layer)),
+            [(-1, 0.9), (0, 0.1), (0, 0.5), (1, 0.0)]
+        );
+    }
+}
@@ -1,12 +1,16 @@
-use crate::GA_FLAVOUR;
+use crate::{FLIPPED_ORIENTATION, GA_FLAVOUR};
 use derive_more::{Add, AddAssign, Neg, Sub, SubAssign};
 use encase::ShaderType;
 use serde::{Deserialize, Serialize};
 use std::{
+    fmt::Display,
     ops::{Div, Mul},
     sync::atomic::Ordering,
 };
 
+/// The epsilon passed to [`Multivector::is_versor`] when deciding whether `x` has an inverse
+const INVERSE_EPSILON: f32 = 1e-5;
+
 #[derive(
     Debug,
     Default,
@@ -17,6 +21,7 @@ use std::{
     Sub,
     SubAssign,
     Neg,
+    PartialEq,
     Serialize,
     Deserialize,
     ShaderType,
@@ -44,6 +49,227 @@ impl Multivector {
         e012: 0.0,
     };
 
+    pub fn components(self) -> [(&'static str, f32); 8] {
+        [
+            ("s", self.s),
+            ("e0", self.e0),
+            ("e1", self.e1),
+            ("e2", self.e2),
+            ("e01", self.e01),
+            ("e02", self.e02),
+            ("e12", self.e12),
+            ("e012", self.e012),
+        ]
+    }
+
+    /// The unit basis blade with the given name, like `e12`
+    pub fn basis_blade(name: &str) -> Option<Self> {
+        Some(match name {
+            "e0" => Self {
+                e0: 1.0,
+                ..Self::ZERO
+            },
+            "e1" => Self {
+                e1: 1.0,
+                ..Self::ZERO
+            },
+            "e2" => Self {
+                e2: 1.0,
+                ..Self::ZERO
+            },
+            "e01" => Self {
+                e01: 1.0,
+                ..Self::ZERO
+            },
+            "e02" => Self {
+                e02: 1.0,
+                ..Self::ZERO
+            },
+            "e12" => Self {
+                e12: 1.0,
+                ..Self::ZERO
+            },
+            "e012" => Self {
+                e012: 1.0,
+                ..Self::ZERO
+            },
+            _ => return None,
+        })
+    }
+
+    /// The counterclockwise angle in radians that a motor rotates by, the opposite of [`Multivector::rotor`].
+    /// Translations do not change the scalar or `e12` parts so this works for any motor
+    pub fn rotation_angle(self) -> f32 {
+        2.0 * (-self.e12).atan2(self.s)
+    }
+
+    /// A rotor that rotates counterclockwise by `angle` radians around the origin
+    pub fn rotor(angle: f32) -> Self {
+        let (sin, cos) = (angle * 0.5).sin_cos();
+        Self {
+            s: cos,
+            e12: -sin,
+            ..Self::ZERO
+        }
+    }
+
+    /// A motor that rotates counterclockwise by `angle` radians around `point`,
+    /// returns `None` for ideal points since there is no finite center to rotate around
+    pub fn rotor_about(point: Self, angle: f32) -> Option<Self> {
+        let (x, y) = point.point_coordinates()?;
+        let translator = Self::translator(x, y);
+        Some(translator * Self::rotor(angle) * translator.reverse())
+    }
+
+    /// `exp` of the normalized point `point` scaled by `-amount / 2`, which rotates counterclockwise by `amount` radians
+    /// around a finite point, the same as [`Multivector::rotor_about`]. Ideal points are normalized by their weight instead
+    /// and translate by `amount`, perpendicular to their direction like a rotation around a point infinitely far away
+    pub fn motor_along(point: Self, amount: f32) -> Self {
+        let point = point.grade2();
+        let generator = if point.magnitude() > 0.0 {
+            point.normalized()
+        } else {
+            let weight = point.e01.hypot(point.e02);
+            if weight > 0.0 { point / weight } else { point }
+        };
+        (generator * (-amount * 0.5)).exp()
+    }
+
+    /// A motor that takes the line `from` onto the line `to`, only using their grade 1 parts.
+    /// Anti-parallel lines could be rotated either way, so they are always rotated by half a turn
+    /// around the point halfway between them that is closest to the origin
+    pub fn rotor_between(from: Self, to: Self) -> Self {
+        let from = from.grade1().normalized();
+        let to = to.grade1().normalized();
+        let motor = Self {
+            s: 1.0,
+            ..Self::ZERO
+        } + to * from;
+        if motor.bulk().sqr_magnitude() > 0.000001 {
+            return motor.normalized();
+        }
+
+        let halfway = (from - to) * 0.5;
+        let center = halfway.wedge(halfway.inner(Self {
+            e12: 1.0,
+            ..Self::ZERO
+        }));
+        Self::rotor_about(center, std::f32::consts::PI).unwrap_or(Self {
+            e12: 1.0,
+            ..Self::ZERO
+        })
+    }
+
+    /// A translator that moves by `(x, y)`, this is only a pure translation in the euclidean flavour
+    pub fn translator(x: f32, y: f32) -> Self {
+        Self {
+            s: 1.0,
+            e01: -0.5 * x,
+            e02: -0.5 * y,
+            ..Self::ZERO
+        }
+    }
+
+    /// The affine matrix acting on homogeneous `(x, y, 1)` coordinates the same way that
+    /// the sandwich `self * point * self.reverse()` acts on points,
+    /// only the even grades are used since those are the ones that make up a motor
+    pub fn to_mat3(self) -> cgmath::Matrix3<f32> {
+        let motor = self.grade0() + self.grade2();
+        let apply = |x: f32, y: f32| {
+            (motor * Self::point(x, y) * motor.reverse())
+                .point_coordinates()
+                .unwrap_or((0.0, 0.0))
+        };
+        let (origin_x, origin_y) = apply(0.0, 0.0);
+        let (x_axis_x, x_axis_y) = apply(1.0, 0.0);
+        let (y_axis_x, y_axis_y) = apply(0.0, 1.0);
+        cgmath::Matrix3::new(
+            x_axis_x - origin_x,
+            x_axis_y - origin_y,
+            0.0,
+            y_axis_x - origin_x,
+            y_axis_y - origin_y,
+            0.0,
+            origin_x,
+            origin_y,
+            1.0,
+        )
+    }
+
+    /// Recovers the motor from a rigid affine matrix, the inverse of `to_mat3`,
+    /// returns `None` if the matrix scales, shears, reflects, or is not affine
+    pub fn from_mat3(matrix: cgmath::Matrix3<f32>) -> Option<Self> {
+        use cgmath::{InnerSpace, SquareMatrix};
+
+        const EPSILON: f32 = 0.0001;
+        let is_affine = matrix.x.z.abs() < EPSILON
+            && matrix.y.z.abs() < EPSILON
+            && (matrix.z.z - 1.0).abs() < EPSILON;
+        let x_axis = matrix.x.truncate();
+        let y_axis = matrix.y.truncate();
+        let is_rigid = (x_axis.magnitude2() - 1.0).abs() < EPSILON
+            && (y_axis.magnitude2() - 1.0).abs() < EPSILON
+            && x_axis.dot(y_axis).abs() < EPSILON
+            && matrix.determinant() > 0.0;
+        if !is_affine || !is_rigid {
+            return None;
+        }
+
+        let angle = x_axis.y.atan2(x_axis.x);
+        Some(Self::translator(matrix.z.x, matrix.z.y) * Self::rotor(angle))
+    }
+
+    pub fn point(x: f32, y: f32) -> Self {
+        Self {
+            e01: y,
+            e02: -x,
+            e12: 1.0,
+            ..Self::ZERO
+        }
+    }
+
+    /// Returns `None` for ideal points (directions), which have no finite coordinates
+    pub fn point_coordinates(self) -> Option<(f32, f32)> {
+        if self.e12.abs() < 0.000001 {
+            None
+        } else {
+            Some((-self.e02 / self.e12, self.e01 / self.e12))
+        }
+    }
+
+    pub fn is_finite(self) -> bool {
+        let Self {
+            s,
+            e0,
+            e1,
+            e2,
+            e01,
+            e02,
+            e12,
+            e012,
+        } = self;
+        [s, e0, e1, e2, e01, e02, e12, e012]
+            .into_iter()
+            .all(f32::is_finite)
+    }
+
+    pub fn scalar(self) -> f32 {
+        self.s
+    }
+
+    /// Whether every part other than the scalar is zero
+    pub fn is_scalar(self) -> bool {
+        self == self.grade0()
+    }
+
+    /// Whether `self * self.reverse()` is a nonzero scalar, which is true of motors, reflections, and their products,
+    /// so this checks that `self` can be used as a transform. The non-scalar parts are compared relative to the scalar
+    pub fn is_versor(self, epsilon: f32) -> bool {
+        let norm = self * self.reverse();
+        norm.s.abs() > epsilon
+            && (norm - norm.grade0()).approx_zero(epsilon * norm.s.abs()) == Self::ZERO
+    }
+
     pub fn grade0(self) -> Self {
         Self {
             s: self.s,
@@ -76,6 +302,29 @@ impl Multivector {
         }
     }
 
+    /// The components without `e0`, which are the ones the euclidean norm measures
+    pub fn bulk(self) -> Self {
+        Self {
+            s: self.s,
+            e1: self.e1,
+            e2: self.e2,
+            e12: self.e12,
+            ..Self::ZERO
+        }
+    }
+
+    /// The components containing `e0`, which square to zero in the euclidean flavour,
+    /// `bulk() + weight()` is always the original multivector
+    pub fn weight(self) -> Self {
+        Self {
+            e0: self.e0,
+            e01: self.e01,
+            e02: self.e02,
+            e012: self.e012,
+            ..Self::ZERO
+        }
+    }
+
     pub fn grade(self, grade: usize) -> Multivector {
         match grade {
             0 => self.grade0(),
@@ -86,6 +335,60 @@ impl Multivector {
         }
     }
 
+    /// The size of the largest component, which gives a rough idea of the scale of the whole value
+    pub fn max_component_abs(self) -> f32 {
+        self.components()
+            .into_iter()
+            .map(|(_, value)| value.abs())
+            .fold(0.0, f32::max)
+    }
+
+    /// The size of the smallest component, zero unless every component is in use
+    pub fn min_component_abs(self) -> f32 {
+        self.components()
+            .into_iter()
+            .map(|(_, value)| value.abs())
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Which of grades 0 to 3 have a component with a magnitude of at least `epsilon`, the same cutoff as
+    /// [`Multivector::approx_zero`]. An `epsilon` of 0 counts any nonzero component, so numerical noise counts too
+    pub fn grades(self, epsilon: f32) -> [bool; 4] {
+        [0, 1, 2, 3].map(|grade| self.grade(grade).approx_zero(epsilon) != Self::ZERO)
+    }
+
+    /// Mixes each grade separately, `weights[g]` of 0 keeps grade `g` of `self` and 1 takes it from `other`.
+    /// Blending only some grades rarely gives a valid point, line, or motor, so this is for showing
+    /// in between states rather than for constructing objects
+    pub fn blend_grades(self, other: Self, weights: [f32; 4]) -> Self {
+        (0..4)
+            .map(|grade| {
+                self.grade(grade) * (1.0 - weights[grade]) + other.grade(grade) * weights[grade]
+            })
+            .fold(Self::ZERO, |sum, part| sum + part)
+    }
+
+    /// Zeroes every component whose magnitude is below `epsilon`,
+    /// for hiding the numerical noise that builds up from chained products
+    pub fn approx_zero(self, epsilon: f32) -> Self {
+        let clean = |value: f32| if value.abs() < epsilon { 0.0 } else { value };
+        Self {
+            s: clean(self.s),
+            e0: clean(self.e0),
+            e1: clean(self.e1),
+            e2: clean(self.e2),
+            e01: clean(self.e01),
+            e02: clean(self.e02),
+            e12: clean(self.e12),
+            e012: clean(self.e012),
+        }
+    }
+
+    /// Whether every component of `self` is within `epsilon` of the same component of `other`
+    pub fn approx_eq(self, other: Self, epsilon: f32) -> bool {
+        (self - other).approx_zero(epsilon) == Self::ZERO
+    }
+
     pub fn wedge(self, other: Self) -> Self {
         let mut result = Self::ZERO;
         for j in 0..=3 {
@@ -96,6 +399,9 @@ impl Multivector {
         result
     }
 
+    /// The symmetric inner product used by the `|` operator, the grade `|j - k|` part of the
+    /// product of every grade `j` part of `self` with every grade `k` part of `other`,
+    /// so scalars are included and `s | x` is `s * x` for any grade of `x`
     pub fn inner(self, other: Self) -> Self {
         let mut result = Self::ZERO;
         for j in 0..=3 {
@@ -106,6 +412,52 @@ impl Multivector {
         result
     }
 
+    /// The Hestenes inner product, the same as [`Multivector::inner`] except that any term with a
+    /// scalar on either side is dropped, so `s | x` is always zero
+    pub fn hestenes_inner(self, other: Self) -> Self {
+        let mut result = Self::ZERO;
+        for j in 1..=3 {
+            for k in 1..=3 {
+                result += (self.grade(j) * other.grade(k)).grade(j.abs_diff(k));
+            }
+        }
+        result
+    }
+
+    pub fn dot(self, other: Self) -> f32 {
+        (self * other).s
+    }
+
+    /// The grade 2 part of the commutator `(self * other - other * self) / 2`,
+    /// for bivectors this is the lie bracket of the motions they generate
+    pub fn lie_bracket(self, other: Self) -> Self {
+        ((self * other - other * self) * 0.5).grade2()
+    }
+
+    /// The line through `point` that is perpendicular to `self`, which is `self | point`
+    pub fn perpendicular(self, point: Self) -> Self {
+        self.grade1().inner(point.grade2())
+    }
+
+    /// The signed perpendicular distance from `point` to `line`, positive on the side that the line's normal
+    /// (its `e1` and `e2` parts) points towards. Both are normalized first, and `None` is returned if either is ideal
+    pub fn point_line_distance(point: Self, line: Self) -> Option<f32> {
+        let (point, line) = (point.grade2(), line.grade1());
+        if point.e12.abs() < 0.000001 || line.e1.hypot(line.e2) < 0.000001 {
+            return None;
+        }
+        Some((point / point.e12).regressive(line.normalized()).s)
+    }
+
+    /// The line through `point` with the same direction as `self`, the perpendicular of the perpendicular,
+    /// which is negated to keep the orientation of `self`. Returns `None` for ideal points
+    pub fn parallel(self, point: Self) -> Option<Self> {
+        if point.e12.abs() < 0.000001 {
+            return None;
+        }
+        Some(-self.perpendicular(point).inner(point.grade2()))
+    }
+
     pub fn regressive(self, other: Self) -> Self {
         self.dual().wedge(other.dual()).dual_inverse()
     }
@@ -133,7 +485,64 @@ impl Multivector {
         }
     }
 
+    /// Negates the odd grades
+    pub fn grade_involution(self) -> Self {
+        let Self {
+            s,
+            e0,
+            e1,
+            e2,
+            e01,
+            e02,
+            e12,
+            e012,
+        } = self;
+        Self {
+            s,
+            e0: -e0,
+            e1: -e1,
+            e2: -e2,
+            e01,
+            e02,
+            e12,
+            e012: -e012,
+        }
+    }
+
+    /// Reflects across the line `mirror`, which is normalized first.
+    /// A line is an odd versor, so the sandwich is applied to the grade involution of `self`,
+    /// without it reflected lines would come out negated while points would not
+    pub fn reflect(self, mirror: Self) -> Self {
+        let mirror = mirror.normalized();
+        mirror * self.grade_involution() * mirror
+    }
+
     pub fn dual(self) -> Self {
+        self.unoriented_dual()
+            .oriented(FLIPPED_ORIENTATION.load(Ordering::Relaxed))
+    }
+
+    pub fn dual_inverse(self) -> Self {
+        self.unoriented_dual_inverse()
+            .oriented(FLIPPED_ORIENTATION.load(Ordering::Relaxed))
+    }
+
+    /// The left complement, `self.antidual().wedge(self)` is the pseudoscalar for every basis blade,
+    /// where `dual` is the right complement with `self.wedge(self.dual())` being the pseudoscalar.
+    /// Flipping the orientation negates the pseudoscalar, so like `dual` this is negated too.
+    /// The two complements differ by `(-1)^(g(n - g))` for a blade of grade `g`, which is even for
+    /// the 3 basis vectors here, so they agree in either orientation
+    pub fn antidual(self) -> Self {
+        self.unoriented_antidual()
+            .oriented(FLIPPED_ORIENTATION.load(Ordering::Relaxed))
+    }
+
+    /// Negates a complement when the orientation is flipped, which negates the pseudoscalar
+    fn oriented(self, flipped: bool) -> Self {
+        if flipped { -self } else { self }
+    }
+
+    fn unoriented_dual(self) -> Self {
         let Self {
             s,
             e0,
@@ -156,7 +565,31 @@ impl Multivector {
         }
     }
 
-    pub fn dual_inverse(self) -> Self {
+    fn unoriented_antidual(self) -> Self {
+        let Self {
+            s,
+            e0,
+            e1,
+            e2,
+            e01,
+            e02,
+            e12,
+            e012,
+        } = self;
+        // each blade maps to the one that completes it to `e012` when wedged on its left
+        Self {
+            s: e012,
+            e0: e12,
+            e1: -e02,
+            e2: e01,
+            e01: e2,
+            e02: -e1,
+            e12: e0,
+            e012: s,
+        }
+    }
+
+    fn unoriented_dual_inverse(self) -> Self {
         let Self {
             s,
             e0,
@@ -187,6 +620,8 @@ impl Multivector {
         self.sqr_magnitude().abs().sqrt()
     }
 
+    /// Null multivectors (such as the pseudoscalar `e012`, which squares to zero) have no
+    /// magnitude to divide by, so they are returned unchanged
     pub fn normalized(self) -> Self {
         let magnitude = self.magnitude();
         if magnitude > 0.0 {
@@ -196,8 +631,60 @@ impl Multivector {
         }
     }
 
+    /// `self.reverse()` divided by `self * self.reverse()`, which is only the inverse when that product is
+    /// a nonzero scalar, as it is for versors. Returns `None` when [`Multivector::is_versor`] is false,
+    /// which covers null multivectors (such as the pseudoscalar `e012`) and zero divisors like `1 + e1`
+    pub fn inverse(self) -> Option<Self> {
+        self.is_versor(INVERSE_EPSILON)
+            .then(|| self.reverse() / (self * self.reverse()).s)
+    }
+
+    /// The signed area of the triangle between three points, positive when they go counterclockwise.
+    /// The join of the points is twice the area scaled by their weights, so it is divided out,
+    /// returns `None` if any point is ideal
+    pub fn triangle_area(a: Self, b: Self, c: Self) -> Option<f32> {
+        let (a, b, c) = (a.grade2(), b.grade2(), c.grade2());
+        if a.e12.abs() < 0.000001 || b.e12.abs() < 0.000001 || c.e12.abs() < 0.000001 {
+            return None;
+        }
+        let weight = a.e12 * b.e12 * c.e12;
+        Some(a.regressive(b).regressive(c).scalar() * 0.5 / weight)
+    }
+
+    /// `self` multiplied by itself `n` times using exponentiation by squaring, negative powers use
+    /// the inverse and `powi(0)` is the scalar 1. Multivectors without an inverse have negative
+    /// powers of zero
+    pub fn powi(self, n: i32) -> Self {
+        let mut base = if n < 0 {
+            self.inverse().unwrap_or(Self::ZERO)
+        } else {
+            self
+        };
+        let mut exponent = n.unsigned_abs();
+        let mut result = Self {
+            s: 1.0,
+            ..Self::ZERO
+        };
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// Picks the trigonometric, linear, or hyperbolic formula from the sign of the square,
+    /// null bivectors like `e01` square to zero so their exponential is just `1 + self`.
+    /// The square is compared with `partial_cmp` so that `-0.0` also counts as null,
+    /// `total_cmp` orders it below zero which would divide by a zero magnitude
     pub fn exp(self) -> Self {
-        match (self * self).s.total_cmp(&0.0) {
+        match (self * self)
+            .s
+            .partial_cmp(&0.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+        {
             std::cmp::Ordering::Less => {
                 let magnitude = self.magnitude();
                 Multivector {
@@ -319,3 +806,473 @@ impl Div<f32> for Multivector {
         self * other.recip()
     }
 }
+
+/// Formats as an expression that the scripting language can parse, like `1 + 2*e1 - e12`,
+/// assuming the basis elements are available as variables with their usual names
+impl Display for Multivector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut first = true;
+        for (name, value) in self.components() {
+            // this also skips `-0.0`, so it never shows up as `-0`
+            if value == 0.0 {
+                continue;
+            }
+
+            match (first, value < 0.0) {
+                (true, false) => {}
+                (true, true) => write!(f, "-")?,
+                (false, false) => write!(f, " + ")?,
+                (false, true) => write!(f, " - ")?,
+            }
+            first = false;
+
+            let value = value.abs();
+            match name {
+                "s" => write!(f, "{value}")?,
+                _ if value == 1.0 => write!(f, "{name}")?,
+                _ => write!(f, "{value}*{name}")?,
+            }
+        }
+        if first {
+            write!(f, "0")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_approx_eq(actual: Multivector, expected: Multivector) {
+        let difference = actual - expected;
+        let components = [
+            difference.s,
+            difference.e0,
+            difference.e1,
+            difference.e2,
+            difference.e01,
+            difference.e02,
+            difference.e12,
+            difference.e012,
+        ];
+        assert!(
+            components.iter().all(|component| component.abs() < 1e-5),
+            "{actual:?} is not {expected:?}"
+        );
+    }
+
+    #[test]
+    fn exp_of_ideal_bivector_is_translator() {
+        let one = Multivector {
+            s: 1.0,
+            ..Multivector::ZERO
+        };
+        let e01 = Multivector {
+            e01: 1.5,
+            ..Multivector::ZERO
+        };
+        let e02 = Multivector {
+            e02: -2.0,
+            ..Multivector::ZERO
+        };
+        assert_approx_eq(e01.exp(), one + e01);
+        assert_approx_eq(e02.exp(), one + e02);
+        assert_approx_eq((e01 + e02).exp(), one + e01 + e02);
+    }
+
+    #[test]
+    fn exp_of_e12_is_rotor() {
+        for angle in [0.3f32, 1.0, -2.5, std::f32::consts::PI] {
+            let rotor = Multivector {
+                e12: angle,
+                ..Multivector::ZERO
+            }
+            .exp();
+            assert_approx_eq(
+                rotor,
+                Multivector {
+                    s: angle.cos(),
+                    e12: angle.sin(),
+                    ..Multivector::ZERO
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn exp_of_mixed_bivector_is_unit_motor() {
+        let bivector = Multivector {
+            e01: 0.5,
+            e02: -1.25,
+            e12: 0.75,
+            ..Multivector::ZERO
+        };
+        let motor = bivector.exp();
+        assert_approx_eq(
+            motor,
+            Multivector {
+                s: 0.75f32.cos(),
+                ..Multivector::ZERO
+            } + bivector * (0.75f32.sin() / 0.75),
+        );
+        assert_approx_eq(
+            motor * motor.reverse(),
+            Multivector {
+                s: 1.0,
+                ..Multivector::ZERO
+            },
+        );
+    }
+
+    #[test]
+    fn inverse_of_versor() {
+        let one = Multivector {
+            s: 1.0,
+            ..Multivector::ZERO
+        };
+        let motor = Multivector::rotor(0.7) * Multivector::translator(1.5, -2.0);
+        let line = Multivector {
+            e0: 0.5,
+            e1: 3.0,
+            e2: -4.0,
+            ..Multivector::ZERO
+        };
+        for versor in [motor, motor * 2.0, line, Multivector::point(1.0, 2.0)] {
+            let inverse = versor.inverse().unwrap();
+            assert_approx_eq(versor * inverse, one);
+            assert_approx_eq(inverse * versor, one);
+        }
+    }
+
+    #[test]
+    fn non_versors_have_no_inverse() {
+        let zero_divisor = Multivector {
+            s: 1.0,
+            e1: 1.0,
+            ..Multivector::ZERO
+        };
+        let mixed = Multivector {
+            s: 1.0,
+            e1: 2.0,
+            e12: 1.0,
+            ..Multivector::ZERO
+        };
+        assert!(zero_divisor.inverse().is_none());
+        assert!(mixed.inverse().is_none());
+    }
+
+    #[test]
+    fn null_multivectors_have_no_inverse() {
+        let pseudoscalar = Multivector {
+            e012: 1.0,
+            ..Multivector::ZERO
+        };
+        let ideal_line = Multivector {
+            e0: 1.0,
+            ..Multivector::ZERO
+        };
+        assert!(Multivector::ZERO.inverse().is_none());
+        assert!(pseudoscalar.inverse().is_none());
+        assert!(ideal_line.inverse().is_none());
+    }
+
+    #[test]
+    fn complements_complete_the_pseudoscalar_in_both_orientations() {
+        let blades = ["e0", "e1", "e2", "e01", "e02", "e12", "e012"]
+            .map(|name| Multivector::basis_blade(name).unwrap());
+        let scalar = Multivector {
+            s: 1.0,
+            ..Multivector::ZERO
+        };
+        // the orientation is passed in rather than set globally, since tests run in parallel
+        for flipped in [false, true] {
+            let dual = |value: Multivector| value.unoriented_dual().oriented(flipped);
+            let antidual = |value: Multivector| value.unoriented_antidual().oriented(flipped);
+            let pseudoscalar = Multivector {
+                e012: 1.0,
+                ..Multivector::ZERO
+            }
+            .oriented(flipped);
+            for blade in blades.into_iter().chain([scalar]) {
+                assert_approx_eq(blade.wedge(dual(blade)), pseudoscalar);
+                assert_approx_eq(antidual(blade).wedge(blade), pseudoscalar);
+                assert_approx_eq(dual(antidual(blade)), blade);
+                assert_approx_eq(antidual(dual(blade)), blade);
+            }
+        }
+    }
+
+    #[test]
+    fn motor_along_origin_is_rotor() {
+        let origin = Multivector::point(0.0, 0.0);
+        assert_approx_eq(
+            Multivector::motor_along(origin, std::f32::consts::PI),
+            Multivector::rotor(std::f32::consts::PI),
+        );
+    }
+
+    #[test]
+    fn motors_and_reflections_are_versors() {
+        let motor = Multivector::translator(1.0, -2.0) * Multivector::rotor(0.7);
+        let reflection =
+            Multivector::basis_blade("e1").unwrap() + Multivector::basis_blade("e0").unwrap() * 3.0;
+        assert!(motor.is_versor(1e-5));
+        assert!(reflection.is_versor(1e-5));
+        assert!((reflection * motor).is_versor(1e-5));
+
+        let mixed = Multivector {
+            s: 1.0,
+            e1: 2.0,
+            e12: 0.5,
+            e012: 1.0,
+            ..Multivector::ZERO
+        };
+        assert!(!mixed.is_versor(1e-5));
+        assert_eq!(mixed.inverse(), None);
+    }
+
+    #[test]
+    fn rotor_by_pi_is_a_half_turn() {
+        let rotor = Multivector::rotor(std::f32::consts::PI);
+        let (x, y) = (rotor * Multivector::point(1.0, 2.0) * rotor.reverse())
+            .point_coordinates()
+            .unwrap();
+        assert!(
+            (x + 1.0).abs() < 1e-5 && (y + 2.0).abs() < 1e-5,
+            "({x}, {y})"
+        );
+        assert!((rotor.rotation_angle() - std::f32::consts::PI).abs() < 1e-5);
+    }
+
+    #[test]
+    fn reflect_points_and_lines() {
+        let x_axis = Multivector::basis_blade("e2").unwrap();
+        let y_axis = Multivector::basis_blade("e1").unwrap();
+        assert_eq!(
+            Multivector::point(1.0, 1.0)
+                .reflect(x_axis)
+                .point_coordinates(),
+            Some((1.0, -1.0))
+        );
+
+        // the line `x = 1` reflected across the y axis is `x = -1`, with its orientation flipped
+        let e0 = Multivector::basis_blade("e0").unwrap();
+        assert_approx_eq((y_axis - e0).reflect(y_axis), -(y_axis + e0));
+    }
+
+    #[test]
+    fn dot_of_basis_vectors() {
+        let e1 = Multivector::basis_blade("e1").unwrap();
+        let e2 = Multivector::basis_blade("e2").unwrap();
+        assert_eq!(e1.dot(e1), 1.0);
+        assert_eq!(e1.dot(e2), 0.0);
+    }
+
+    #[test]
+    fn to_mat3_matches_sandwich() {
+        let motor = Multivector::translator(1.5, -2.0) * Multivector::rotor(0.8);
+        let matrix = motor.to_mat3();
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (-3.0, 2.5)] {
+            let transformed = matrix * cgmath::Vector3::new(x, y, 1.0);
+            let (expected_x, expected_y) = (motor * Multivector::point(x, y) * motor.reverse())
+                .point_coordinates()
+                .unwrap();
+            assert!(
+                (transformed.x - expected_x).abs() < 1e-5
+                    && (transformed.y - expected_y).abs() < 1e-5
+                    && transformed.z == 1.0,
+                "{transformed:?} is not ({expected_x}, {expected_y})"
+            );
+        }
+    }
+
+    #[test]
+    fn from_mat3_round_trips() {
+        let motor = Multivector::translator(1.5, -2.0) * Multivector::rotor(0.8);
+        assert_approx_eq(Multivector::from_mat3(motor.to_mat3()).unwrap(), motor);
+
+        let scaled = motor.to_mat3() * 2.0;
+        assert_eq!(Multivector::from_mat3(scaled), None);
+    }
+
+    #[test]
+    fn display_skips_negative_zero() {
+        let negative_zero = Multivector {
+            s: -0.0,
+            e1: -0.0,
+            ..Multivector::ZERO
+        };
+        assert_eq!(negative_zero.to_string(), "0");
+        let value = Multivector {
+            s: -1.5,
+            e1: -1.0,
+            e02: -0.0,
+            e012: 2.0,
+            ..Multivector::ZERO
+        };
+        assert_eq!(value.to_string(), "-1.5 - e1 + 2*e012");
+    }
+
+    #[test]
+    fn bulk_and_weight_add_up() {
+        let value = Multivector {
+            s: 1.0,
+            e0: 2.0,
+            e1: 3.0,
+            e2: 4.0,
+            e01: 5.0,
+            e02: 6.0,
+            e12: 7.0,
+            e012: 8.0,
+        };
+        assert_eq!(value.bulk() + value.weight(), value);
+        assert_eq!(value.bulk().weight(), Multivector::ZERO);
+        assert_eq!(value.weight().bulk(), Multivector::ZERO);
+    }
+
+    #[test]
+    fn lie_bracket_of_se2() {
+        let [e01, e02, e12] =
+            ["e01", "e02", "e12"].map(|name| Multivector::basis_blade(name).unwrap());
+        // rotating a translation gives the perpendicular translation, and translations commute
+        for (a, b, bracket) in [
+            (e01, e12, e02),
+            (e02, e12, -e01),
+            (e01, e02, Multivector::ZERO),
+            (e12, e12, Multivector::ZERO),
+        ] {
+            assert_eq!(a.lie_bracket(b), bracket, "[{a}, {b}]");
+            assert_eq!(b.lie_bracket(a), -bracket, "[{b}, {a}]");
+        }
+    }
+
+    #[test]
+    fn rotor_between_takes_one_line_onto_another() {
+        let [e0, e1, e2] = ["e0", "e1", "e2"].map(|name| Multivector::basis_blade(name).unwrap());
+        // a general pair, then an anti-parallel pair that is turned by half a turn
+        for (from, to) in [(e1, e2 + e0), (e1 * 2.0, -e1 + e0 * 2.0)] {
+            let motor = Multivector::rotor_between(from, to);
+            assert_approx_eq(motor * from.normalized() * motor.reverse(), to.normalized());
+        }
+    }
+
+    #[test]
+    fn perpendicular_through_point() {
+        let x_axis = Multivector::basis_blade("e2").unwrap();
+        let line = x_axis.perpendicular(Multivector::point(2.0, 3.0));
+        // the line `x = 2` goes through every point with an x of 2
+        for y in [-1.0, 0.0, 3.0, 10.0] {
+            assert_approx_eq(line.wedge(Multivector::point(2.0, y)), Multivector::ZERO);
+        }
+        assert_eq!(line.dot(x_axis), 0.0);
+    }
+
+    #[test]
+    fn parallel_through_point() {
+        let x_axis = Multivector::basis_blade("e2").unwrap();
+        let line = x_axis.parallel(Multivector::point(0.0, 5.0)).unwrap();
+        // the line `y = 5`, keeping the orientation of the x axis
+        for x in [-1.0, 0.0, 3.0, 10.0] {
+            assert_approx_eq(line.wedge(Multivector::point(x, 5.0)), Multivector::ZERO);
+        }
+        assert!(line.dot(x_axis) > 0.0);
+        assert_eq!(
+            x_axis.parallel(Multivector::basis_blade("e01").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn point_line_distance_is_signed() {
+        // the x axis scaled, and a point with a weight other than 1, since both are normalized
+        let x_axis = Multivector::basis_blade("e2").unwrap() * 4.0;
+        let distance = |x, y, weight| {
+            Multivector::point_line_distance(Multivector::point(x, y) * weight, x_axis)
+        };
+        assert_eq!(distance(1.0, 3.0, 1.0), Some(3.0));
+        assert_eq!(distance(-2.0, 3.0, 2.5), Some(3.0));
+        assert_eq!(distance(0.0, -3.0, 1.0), Some(-3.0));
+        assert_eq!(
+            Multivector::point_line_distance(Multivector::basis_blade("e01").unwrap(), x_axis),
+            None
+        );
+    }
+
+    #[test]
+    fn triangle_area_of_unit_right_triangle() {
+        let (a, b, c) = (
+            Multivector::point(0.0, 0.0),
+            Multivector::point(1.0, 0.0),
+            Multivector::point(0.0, 1.0),
+        );
+        assert_eq!(Multivector::triangle_area(a, b, c), Some(0.5));
+        // clockwise is negative, and the weights of the points are divided out
+        assert_eq!(Multivector::triangle_area(a, c, b), Some(-0.5));
+        assert_eq!(Multivector::triangle_area(a * 2.0, b * -3.0, c), Some(0.5));
+        assert_eq!(
+            Multivector::triangle_area(a, b, Multivector::basis_blade("e01").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn powi_of_rotor() {
+        let rotor = Multivector::rotor(0.3);
+        let one = Multivector {
+            s: 1.0,
+            ..Multivector::ZERO
+        };
+        assert_eq!(rotor.powi(0), one);
+        assert_approx_eq(rotor.powi(1), rotor);
+        assert_approx_eq(rotor.powi(5), Multivector::rotor(1.5));
+        assert_approx_eq(rotor.powi(-3), Multivector::rotor(-0.9));
+        // null multivectors have no inverse, so their negative powers are zero
+        let e012 = Multivector::basis_blade("e012").unwrap();
+        assert_eq!(e012.powi(-2), Multivector::ZERO);
+    }
+
+    #[test]
+    fn grade_parts_sum_to_product() {
+        let line =
+            Multivector::basis_blade("e1").unwrap() + Multivector::basis_blade("e0").unwrap();
+        let product = line * Multivector::point(2.0, 3.0);
+        let sum = (0..4).fold(Multivector::ZERO, |sum, grade| sum + product.grade(grade));
+        assert_eq!(sum, product);
+        // a line times a point has a vector and a trivector part
+        assert_eq!(product.grades(0.0), [false, true, false, true]);
+        assert_eq!((product * 1e-6).grades(1e-5), [false; 4]);
+    }
+
+    #[test]
+    fn is_finite_rejects_nan_and_infinity() {
+        assert!(Multivector::rotor(1.0).is_finite());
+        for bad in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let value = Multivector {
+                e02: bad,
+                ..Multivector::ZERO
+            };
+            assert!(!value.is_finite(), "{value:?}");
+        }
+        // dividing by a zero weight is how non-finite values usually show up
+        assert!(!(Multivector::point(1.0, 2.0) / 0.0).is_finite());
+    }
+
+    #[test]
+    fn largest_and_smallest_components() {
+        let value = Multivector {
+            s: -3.0,
+            e0: 0.5,
+            e1: 2.0,
+            e2: -0.25,
+            e01: 1.0,
+            e02: -7.0,
+            e12: 0.75,
+            e012: 4.0,
+        };
+        assert_eq!(value.max_component_abs(), 7.0);
+        assert_eq!(value.min_component_abs(), 0.25);
+        assert_eq!(Multivector::rotor(1.0).min_component_abs(), 0.0);
+        assert_eq!(Multivector::ZERO.max_component_abs(), 0.0);
+    }
+}
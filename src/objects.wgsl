@@ -14,6 +14,9 @@ struct Camera {
     line_thickness: f32,
     point_radius: f32,
     ga_flavour: u32,
+    line_extent: f32,
+    outline_width: f32,
+    line_orientation: u32,
 }
 
 @group(0) @binding(0)
@@ -23,6 +26,9 @@ struct Object {
     value: Multivector,
     color: vec3<f32>,
     layer: f32,
+    point_style: u32,
+    scale: f32,
+    z_order: i32,
 }
 
 struct Objects {
@@ -65,27 +71,44 @@ fn fragment(input: VertexOutput) -> @location(0) vec4<f32> {
     pixel_point = normalized(mul(mul(transform, pixel_point), reverse(transform)));
 
     var color: vec3<f32>;
+    var depth_z_order = 0;
     var depth = 0.0;
     var rendering = false;
 
     for (var i = 0u; i < objects.count; i += 1) {
         let object = objects.data[i];
-        if object.layer < depth {
+        // the z order decides what is on top first, and the layer only between equal z orders
+        if rendering && (object.z_order < depth_z_order || (object.z_order == depth_z_order && object.layer < depth)) {
             continue;
         }
 
         let line = grade1(object.value);
-        if sqr_magnitude(line) > 0.0001 && magnitude(regressive(normalized(line), pixel_point)) <= camera.line_thickness * 0.5 {
-            rendering = true;
-            color = object.color;
-            depth = object.layer;
+        if sqr_magnitude(line) > 0.0001 && within_line_extent(line, pixel_point) {
+            // positive on the side that the line's normal points towards
+            let signed_distance = regressive(normalized(line), pixel_point).s;
+            let distance = abs(signed_distance);
+            let half_thickness = camera.line_thickness * 0.5 * object.scale;
+            if distance <= half_thickness + camera.outline_width {
+                rendering = true;
+                color = outlined_color(object.color, distance, half_thickness);
+                if camera.line_orientation != 0u && signed_distance < 0.0 && distance <= half_thickness {
+                    color *= 0.5;
+                }
+                depth_z_order = object.z_order;
+                depth = object.layer;
+            }
         }
 
         let point = grade2(object.value);
-        if sqr_magnitude(point) > 0.0001 && magnitude(regressive(normalized(point), pixel_point)) <= camera.point_radius {
-            rendering = true;
-            color = object.color;
-            depth = object.layer;
+        if sqr_magnitude(point) > 0.0001 {
+            let radius = camera.point_radius * object.scale;
+            let distance = point_style_distance(object.point_style, point, pixel_point, radius);
+            if distance <= radius + camera.outline_width {
+                rendering = true;
+                color = outlined_color(object.color, distance, radius);
+                depth_z_order = object.z_order;
+                depth = object.layer;
+            }
         }
     }
 
@@ -95,6 +118,46 @@ fn fragment(input: VertexOutput) -> @location(0) vec4<f32> {
     return vec4<f32>(color, 1.0);
 }
 
+fn within_line_extent(line: Multivector, pixel_point: Multivector) -> bool {
+    if camera.line_extent <= 0.0 {
+        return true;
+    }
+
+    var origin: Multivector;
+    origin.e12 = 1.0;
+
+    let perpendicular = inner(normalized(line), origin);
+    return magnitude(regressive(normalized(perpendicular), pixel_point)) <= camera.line_extent;
+}
+
+// a distance that is at most the radius inside the shape for the given point style,
+// 0 is a disc, 1 is a ring, 2 is a cross, 3 is a square
+fn point_style_distance(point_style: u32, point: Multivector, pixel_point: Multivector, radius: f32) -> f32 {
+    let distance = magnitude(regressive(normalized(point), pixel_point));
+    if point_style == 1u {
+        return abs(distance - radius * 0.8) + radius * 0.8;
+    }
+    if point_style == 2u || point_style == 3u {
+        let offset = abs(vec2<f32>(
+            pixel_point.e02 / pixel_point.e12 - point.e02 / point.e12,
+            pixel_point.e01 / pixel_point.e12 - point.e01 / point.e12,
+        ));
+        let far = max(offset.x, offset.y);
+        if point_style == 2u {
+            return max(far, min(offset.x, offset.y) * 4.0);
+        }
+        return far;
+    }
+    return distance;
+}
+
+fn outlined_color(color: vec3<f32>, distance: f32, radius: f32) -> vec3<f32> {
+    if distance <= radius {
+        return color;
+    }
+    return vec3<f32>(0.0);
+}
+
 struct Multivector {
     s: f32,
     e0: f32,
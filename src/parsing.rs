@@ -1,7 +1,10 @@
 use derive_more::Display;
 use thiserror::Error;
 
-use crate::lexer::{Lexer, LexerError, LexerErrorKind, Location, Token, TokenKind};
+use crate::{
+    lexer::{Lexer, LexerError, LexerErrorKind, Location, Token, TokenKind},
+    multivector::Multivector,
+};
 
 #[derive(Debug, Error)]
 #[error("{location}: {kind}")]
@@ -27,6 +30,10 @@ pub enum ParseErrorKind<'source> {
     UnexpectedEOI,
     #[display("Unexpected token '{_0}'")]
     UnexpectedToken(Token<'source>),
+    #[display(
+        "Numbers have no exponent notation, write '* {_0}' to multiply by the variable '{_0}'"
+    )]
+    ExponentNotation(&'source str),
 }
 
 #[derive(Debug)]
@@ -48,9 +55,62 @@ pub enum AstStatementKind<'source> {
 #[derive(Debug)]
 pub struct AstExpression<'source> {
     pub location: Location,
+    /// The location just after the last token of the expression
+    pub end: Location,
     pub kind: AstExpressionKind<'source>,
 }
 
+impl<'source> AstExpression<'source> {
+    /// Calls `f` with every variable name read by this expression, in source order
+    pub fn for_each_name(&self, f: &mut impl FnMut(&'source str, &Token<'source>)) {
+        match self.kind {
+            AstExpressionKind::Name {
+                name,
+                ref name_token,
+            } => f(name, name_token),
+            AstExpressionKind::Number { .. } => {}
+            AstExpressionKind::Unary { ref operand, .. } => operand.for_each_name(f),
+            AstExpressionKind::Binary {
+                ref left,
+                ref right,
+                ..
+            } => {
+                left.for_each_name(f);
+                right.for_each_name(f);
+            }
+            AstExpressionKind::Ternary {
+                ref first,
+                ref second,
+                ref third,
+                ..
+            } => {
+                first.for_each_name(f);
+                second.for_each_name(f);
+                third.for_each_name(f);
+            }
+        }
+    }
+
+    /// How many expressions make up this one, which is the most evaluation budget it can use
+    pub fn node_count(&self) -> usize {
+        1 + match self.kind {
+            AstExpressionKind::Name { .. } | AstExpressionKind::Number { .. } => 0,
+            AstExpressionKind::Unary { ref operand, .. } => operand.node_count(),
+            AstExpressionKind::Binary {
+                ref left,
+                ref right,
+                ..
+            } => left.node_count() + right.node_count(),
+            AstExpressionKind::Ternary {
+                ref first,
+                ref second,
+                ref third,
+                ..
+            } => first.node_count() + second.node_count() + third.node_count(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum AstExpressionKind<'source> {
     Name {
@@ -72,6 +132,13 @@ pub enum AstExpressionKind<'source> {
         operator_token: Token<'source>,
         right: Box<AstExpression<'source>>,
     },
+    Ternary {
+        operator: TernaryOperator,
+        operator_token: Token<'source>,
+        first: Box<AstExpression<'source>>,
+        second: Box<AstExpression<'source>>,
+        third: Box<AstExpression<'source>>,
+    },
 }
 
 #[derive(Debug)]
@@ -86,6 +153,10 @@ pub enum UnaryOperator {
     ASin,
     ACos,
     Exp,
+    Inverse,
+    Rotor,
+    IsVersor,
+    AntiDual,
 }
 
 #[derive(Debug)]
@@ -97,6 +168,25 @@ pub enum BinaryOperator {
     Wedge,
     Inner,
     Regressive,
+    Dot,
+    Translator,
+    RotorAbout,
+    Reflect,
+    LieBracket,
+    RotorBetween,
+    Perpendicular,
+    Parallel,
+    HestenesInner,
+    MotorAlong,
+    DistPointLine,
+}
+
+#[derive(Debug)]
+pub enum TernaryOperator {
+    Clamp,
+    SmoothStep,
+    TriangleArea,
+    BlendGrades,
 }
 
 pub fn parse(source: &str) -> Result<Vec<AstStatement<'_>>, ParseError<'_>> {
@@ -109,17 +199,83 @@ pub fn parse(source: &str) -> Result<Vec<AstStatement<'_>>, ParseError<'_>> {
     Ok(statements)
 }
 
+/// Like [`parse`], but a last statement that ends in a binary operator is treated as still being typed,
+/// so it is left out instead of being an error, and its location is returned as well.
+/// This is only for live feedback while editing, anything else should use [`parse`]
+pub fn parse_lenient(
+    source: &str,
+) -> Result<(Vec<AstStatement<'_>>, Option<Location>), ParseError<'_>> {
+    let mut parser = Parser::new(source);
+
+    let mut statements = vec![];
+    while let Some(token) = parser.lexer.peek_token()? {
+        match parser.parse_statement() {
+            Ok(statement) => statements.push(statement),
+            Err(ParseError {
+                kind: ParseErrorKind::UnexpectedEOI,
+                ..
+            }) if ends_with_binary_operator(source) => {
+                return Ok((statements, Some(token.location)));
+            }
+            Err(error) => return Err(error),
+        }
+    }
+    Ok((statements, None))
+}
+
+fn ends_with_binary_operator(source: &str) -> bool {
+    let mut lexer = Lexer::new(source);
+    let mut last = None;
+    while let Ok(Some(token)) = lexer.next_token() {
+        last = Some(token.kind);
+    }
+    matches!(
+        last,
+        Some(
+            TokenKind::Plus
+                | TokenKind::Minus
+                | TokenKind::Asterisk
+                | TokenKind::Slash
+                | TokenKind::Caret
+                | TokenKind::Pipe
+                | TokenKind::Ampersand
+        )
+    )
+}
+
+/// Parses a single expression that makes up the whole of `source`
+pub fn parse_expression(source: &str) -> Result<AstExpression<'_>, ParseError<'_>> {
+    let mut parser = Parser::new(source);
+
+    let expression = parser.parse_expression()?;
+    if let Some(token) = parser.lexer.next_token()? {
+        return Err(ParseError {
+            location: token.location,
+            kind: ParseErrorKind::UnexpectedToken(token),
+        });
+    }
+    Ok(expression)
+}
+
 struct Parser<'source> {
     lexer: Lexer<'source>,
 }
 
 macro_rules! expect_token {
+    // the token on its own, or a tuple of it and the names bound by the pattern
+    (@result $token:ident) => {
+        $token
+    };
+    (@result $token:ident $(, $pattern_names:ident)+) => {
+        ($token $(, $pattern_names)+)
+    };
     ($parser:ident, $pattern:pat $(, $pattern_names:ident)*) => {
         match $parser.lexer.next_token() {
             Ok(Some(token @ Token {
                 location: _,
+                end: _,
                 kind: $pattern,
-            })) => Ok((token $(, $pattern_names)*)),
+            })) => Ok(expect_token!(@result token $(, $pattern_names)*)),
             #[allow(unreachable_patterns)]
             Ok(Some(token)) => Err(ParseError {
                 location: token.location,
@@ -147,7 +303,7 @@ impl<'source> Parser<'source> {
         let value = self.parse_expression()?;
         expect_token!(self, TokenKind::Semicolon)?;
         Ok(AstStatement {
-            location: equals_token.location,
+            location: name_token.location,
             kind: AstStatementKind::Assignment {
                 name,
                 name_token,
@@ -176,6 +332,7 @@ impl<'source> Parser<'source> {
             let operand = self.parse_binary_expression(usize::MAX)?;
             AstExpression {
                 location: operator_token.location,
+                end: self.lexer.location(),
                 kind: AstExpressionKind::Unary {
                     operator,
                     operator_token,
@@ -206,6 +363,7 @@ impl<'source> Parser<'source> {
             let right = self.parse_binary_expression(precedence)?;
             left = AstExpression {
                 location: operator_token.location,
+                end: self.lexer.location(),
                 kind: AstExpressionKind::Binary {
                     left: Box::new(left),
                     operator,
@@ -221,25 +379,83 @@ impl<'source> Parser<'source> {
         Ok(match expect_token!(self, _)? {
             name_token @ Token {
                 location,
+                end: _,
                 kind: TokenKind::Name(name),
             } => AstExpression {
                 location,
+                end: self.lexer.location(),
                 kind: AstExpressionKind::Name { name, name_token },
             },
 
             number_token @ Token {
                 location,
+                end: _,
                 kind: TokenKind::Number(number),
-            } => AstExpression {
-                location,
-                kind: AstExpressionKind::Number {
-                    number,
-                    number_token,
-                },
-            },
+            } => {
+                let number_expression = AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Number {
+                        number,
+                        number_token,
+                    },
+                };
+
+                // a number immediately followed by a name with no whitespace between them, like `2e1` or `2x`,
+                // is an implicit multiplication `2 * e1`. Only names are multiplied this way, `2 (x)` is an error.
+                // There is no exponent notation for numbers, so anything that looks like one, `2e5` or `2e-5`,
+                // is an error rather than silently multiplying by a variable called `e5` or `e`
+                match self.lexer.peek_token()? {
+                    Some(Token {
+                        location: name_location,
+                        end: _,
+                        kind: TokenKind::Name(_),
+                    }) if name_location.position == self.lexer.location().position => {
+                        let (name_token, name) = expect_token!(self, TokenKind::Name(name), name)?;
+                        let digit_exponent = name.strip_prefix('e').is_some_and(|digits| {
+                            !digits.is_empty() && digits.bytes().all(|digit| digit.is_ascii_digit())
+                        }) && Multivector::basis_blade(name).is_none();
+                        let signed_exponent = name == "e"
+                            && matches!(
+                                self.lexer.peek_token()?,
+                                Some(Token {
+                                    location: sign_location,
+                                    end: _,
+                                    kind: TokenKind::Plus | TokenKind::Minus,
+                                }) if sign_location.position == name_token.end.position
+                            );
+                        if digit_exponent || signed_exponent {
+                            return Err(ParseError {
+                                location: name_location,
+                                kind: ParseErrorKind::ExponentNotation(name),
+                            });
+                        }
+                        AstExpression {
+                            location,
+                            end: self.lexer.location(),
+                            kind: AstExpressionKind::Binary {
+                                left: Box::new(number_expression),
+                                operator: BinaryOperator::Multiply,
+                                operator_token: Token {
+                                    location: name_location,
+                                    end: name_location,
+                                    kind: TokenKind::Asterisk,
+                                },
+                                right: Box::new(AstExpression {
+                                    location: name_location,
+                                    end: self.lexer.location(),
+                                    kind: AstExpressionKind::Name { name, name_token },
+                                }),
+                            },
+                        }
+                    }
+                    _ => number_expression,
+                }
+            }
 
             operator_token @ Token {
                 location,
+                end: _,
                 kind: TokenKind::NormalizeKeyword,
             } => {
                 expect_token!(self, TokenKind::OpenParenthesis)?;
@@ -247,6 +463,7 @@ impl<'source> Parser<'source> {
                 expect_token!(self, TokenKind::CloseParenthesis)?;
                 AstExpression {
                     location,
+                    end: self.lexer.location(),
                     kind: AstExpressionKind::Unary {
                         operator: UnaryOperator::Normalise,
                         operator_token,
@@ -257,6 +474,7 @@ impl<'source> Parser<'source> {
 
             operator_token @ Token {
                 location,
+                end: _,
                 kind: TokenKind::MagnitudeKeyword,
             } => {
                 expect_token!(self, TokenKind::OpenParenthesis)?;
@@ -264,6 +482,7 @@ impl<'source> Parser<'source> {
                 expect_token!(self, TokenKind::CloseParenthesis)?;
                 AstExpression {
                     location,
+                    end: self.lexer.location(),
                     kind: AstExpressionKind::Unary {
                         operator: UnaryOperator::Magnitude,
                         operator_token,
@@ -274,6 +493,7 @@ impl<'source> Parser<'source> {
 
             operator_token @ Token {
                 location,
+                end: _,
                 kind: TokenKind::SinKeyword,
             } => {
                 expect_token!(self, TokenKind::OpenParenthesis)?;
@@ -281,6 +501,7 @@ impl<'source> Parser<'source> {
                 expect_token!(self, TokenKind::CloseParenthesis)?;
                 AstExpression {
                     location,
+                    end: self.lexer.location(),
                     kind: AstExpressionKind::Unary {
                         operator: UnaryOperator::Sin,
                         operator_token,
@@ -291,6 +512,7 @@ impl<'source> Parser<'source> {
 
             operator_token @ Token {
                 location,
+                end: _,
                 kind: TokenKind::CosKeyword,
             } => {
                 expect_token!(self, TokenKind::OpenParenthesis)?;
@@ -298,6 +520,7 @@ impl<'source> Parser<'source> {
                 expect_token!(self, TokenKind::CloseParenthesis)?;
                 AstExpression {
                     location,
+                    end: self.lexer.location(),
                     kind: AstExpressionKind::Unary {
                         operator: UnaryOperator::Cos,
                         operator_token,
@@ -308,6 +531,7 @@ impl<'source> Parser<'source> {
 
             operator_token @ Token {
                 location,
+                end: _,
                 kind: TokenKind::ASinKeyword,
             } => {
                 expect_token!(self, TokenKind::OpenParenthesis)?;
@@ -315,6 +539,7 @@ impl<'source> Parser<'source> {
                 expect_token!(self, TokenKind::CloseParenthesis)?;
                 AstExpression {
                     location,
+                    end: self.lexer.location(),
                     kind: AstExpressionKind::Unary {
                         operator: UnaryOperator::ASin,
                         operator_token,
@@ -325,6 +550,7 @@ impl<'source> Parser<'source> {
 
             operator_token @ Token {
                 location,
+                end: _,
                 kind: TokenKind::ACosKeyword,
             } => {
                 expect_token!(self, TokenKind::OpenParenthesis)?;
@@ -332,6 +558,7 @@ impl<'source> Parser<'source> {
                 expect_token!(self, TokenKind::CloseParenthesis)?;
                 AstExpression {
                     location,
+                    end: self.lexer.location(),
                     kind: AstExpressionKind::Unary {
                         operator: UnaryOperator::ACos,
                         operator_token,
@@ -342,6 +569,7 @@ impl<'source> Parser<'source> {
 
             operator_token @ Token {
                 location,
+                end: _,
                 kind: TokenKind::ExpKeyword,
             } => {
                 expect_token!(self, TokenKind::OpenParenthesis)?;
@@ -349,6 +577,7 @@ impl<'source> Parser<'source> {
                 expect_token!(self, TokenKind::CloseParenthesis)?;
                 AstExpression {
                     location,
+                    end: self.lexer.location(),
                     kind: AstExpressionKind::Unary {
                         operator: UnaryOperator::Exp,
                         operator_token,
@@ -357,8 +586,427 @@ impl<'source> Parser<'source> {
                 }
             }
 
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::InverseKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let operand = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Unary {
+                        operator: UnaryOperator::Inverse,
+                        operator_token,
+                        operand: Box::new(operand),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::DotKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let left = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let right = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Binary {
+                        left: Box::new(left),
+                        operator: BinaryOperator::Dot,
+                        operator_token,
+                        right: Box::new(right),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::RotorKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let operand = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Unary {
+                        operator: UnaryOperator::Rotor,
+                        operator_token,
+                        operand: Box::new(operand),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::TranslatorKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let left = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let right = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Binary {
+                        left: Box::new(left),
+                        operator: BinaryOperator::Translator,
+                        operator_token,
+                        right: Box::new(right),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::RotorAboutKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let left = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let right = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Binary {
+                        left: Box::new(left),
+                        operator: BinaryOperator::RotorAbout,
+                        operator_token,
+                        right: Box::new(right),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::ReflectKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let left = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let right = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Binary {
+                        left: Box::new(left),
+                        operator: BinaryOperator::Reflect,
+                        operator_token,
+                        right: Box::new(right),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::BracketKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let left = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let right = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Binary {
+                        left: Box::new(left),
+                        operator: BinaryOperator::LieBracket,
+                        operator_token,
+                        right: Box::new(right),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::RotorBetweenKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let left = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let right = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Binary {
+                        left: Box::new(left),
+                        operator: BinaryOperator::RotorBetween,
+                        operator_token,
+                        right: Box::new(right),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::ClampKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let first = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let second = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let third = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Ternary {
+                        operator: TernaryOperator::Clamp,
+                        operator_token,
+                        first: Box::new(first),
+                        second: Box::new(second),
+                        third: Box::new(third),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::SmoothStepKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let first = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let second = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let third = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Ternary {
+                        operator: TernaryOperator::SmoothStep,
+                        operator_token,
+                        first: Box::new(first),
+                        second: Box::new(second),
+                        third: Box::new(third),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::PerpendicularKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let left = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let right = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Binary {
+                        left: Box::new(left),
+                        operator: BinaryOperator::Perpendicular,
+                        operator_token,
+                        right: Box::new(right),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::ParallelKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let left = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let right = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Binary {
+                        left: Box::new(left),
+                        operator: BinaryOperator::Parallel,
+                        operator_token,
+                        right: Box::new(right),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::HestenesKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let left = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let right = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Binary {
+                        left: Box::new(left),
+                        operator: BinaryOperator::HestenesInner,
+                        operator_token,
+                        right: Box::new(right),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::AreaKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let first = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let second = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let third = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Ternary {
+                        operator: TernaryOperator::TriangleArea,
+                        operator_token,
+                        first: Box::new(first),
+                        second: Box::new(second),
+                        third: Box::new(third),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::IsVersorKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let operand = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Unary {
+                        operator: UnaryOperator::IsVersor,
+                        operator_token,
+                        operand: Box::new(operand),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::MotorAlongKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let left = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let right = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Binary {
+                        left: Box::new(left),
+                        operator: BinaryOperator::MotorAlong,
+                        operator_token,
+                        right: Box::new(right),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::AntiDualKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let operand = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Unary {
+                        operator: UnaryOperator::AntiDual,
+                        operator_token,
+                        operand: Box::new(operand),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::BlendGradesKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let first = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let second = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let third = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Ternary {
+                        operator: TernaryOperator::BlendGrades,
+                        operator_token,
+                        first: Box::new(first),
+                        second: Box::new(second),
+                        third: Box::new(third),
+                    },
+                }
+            }
+
+            operator_token @ Token {
+                location,
+                end: _,
+                kind: TokenKind::DistPointLineKeyword,
+            } => {
+                expect_token!(self, TokenKind::OpenParenthesis)?;
+                let left = self.parse_expression()?;
+                expect_token!(self, TokenKind::Comma)?;
+                let right = self.parse_expression()?;
+                expect_token!(self, TokenKind::CloseParenthesis)?;
+                AstExpression {
+                    location,
+                    end: self.lexer.location(),
+                    kind: AstExpressionKind::Binary {
+                        left: Box::new(left),
+                        operator: BinaryOperator::DistPointLine,
+                        operator_token,
+                        right: Box::new(right),
+                    },
+                }
+            }
+
             Token {
                 location: _,
+                end: _,
                 kind: TokenKind::OpenParenthesis,
             } => {
                 let expression = self.parse_expression()?;
@@ -375,3 +1023,162 @@ impl<'source> Parser<'source> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_value(source: &str) -> Result<AstExpression<'_>, ParseError<'_>> {
+        let mut statements = parse(source)?;
+        assert_eq!(statements.len(), 1);
+        match statements.remove(0).kind {
+            AstStatementKind::Assignment { value, .. } => Ok(value),
+        }
+    }
+
+    fn assert_implicit_multiplication(source: &str, expected_number: f32, expected_name: &str) {
+        let value = parse_value(source).unwrap();
+        let AstExpressionKind::Binary {
+            left,
+            operator: BinaryOperator::Multiply,
+            right,
+            ..
+        } = value.kind
+        else {
+            panic!("{source} is not a multiplication: {value:?}");
+        };
+        assert!(
+            matches!(left.kind, AstExpressionKind::Number { number, .. } if number == expected_number)
+        );
+        assert!(
+            matches!(right.kind, AstExpressionKind::Name { name, .. } if name == expected_name)
+        );
+    }
+
+    #[test]
+    fn number_followed_by_name_is_multiplication() {
+        assert_implicit_multiplication("x = 2e1;", 2.0, "e1");
+        assert_implicit_multiplication("x = 0.5e012;", 0.5, "e012");
+        assert_implicit_multiplication("x = 2x;", 2.0, "x");
+    }
+
+    #[test]
+    fn number_separated_from_name_is_an_error() {
+        assert!(matches!(
+            parse("x = 2 e1;"),
+            Err(ParseError {
+                kind: ParseErrorKind::UnexpectedToken(Token {
+                    kind: TokenKind::Name("e1"),
+                    ..
+                }),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn number_followed_by_parentheses_is_an_error() {
+        for source in ["x = 2 (x);", "x = 2(x);"] {
+            assert!(matches!(
+                parse(source),
+                Err(ParseError {
+                    kind: ParseErrorKind::UnexpectedToken(Token {
+                        kind: TokenKind::OpenParenthesis,
+                        ..
+                    }),
+                    ..
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn exponent_notation_is_an_error() {
+        assert!(matches!(
+            parse("x = 2e5;"),
+            Err(ParseError {
+                kind: ParseErrorKind::ExponentNotation("e5"),
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse("x = 2e-5;"),
+            Err(ParseError {
+                kind: ParseErrorKind::ExponentNotation("e"),
+                ..
+            })
+        ));
+        // with whitespace the sign is a subtraction, so this is `2 * e - 5`
+        assert!(parse("x = 2e - 5;").is_ok());
+    }
+
+    #[test]
+    fn builtin_names_can_be_variables() {
+        let statements = parse("dot = 2; area = dot(dot, dot);").unwrap();
+        assert_eq!(statements.len(), 2);
+        let AstStatementKind::Assignment {
+            name, ref value, ..
+        } = statements[1].kind;
+        assert_eq!(name, "area");
+        let AstExpressionKind::Binary {
+            ref left,
+            operator: BinaryOperator::Dot,
+            ref right,
+            ..
+        } = value.kind
+        else {
+            panic!("{value:?} is not a call to dot");
+        };
+        assert!(matches!(
+            left.kind,
+            AstExpressionKind::Name { name: "dot", .. }
+        ));
+        assert!(matches!(
+            right.kind,
+            AstExpressionKind::Name { name: "dot", .. }
+        ));
+    }
+
+    #[test]
+    fn statements_sharing_a_line_have_their_own_locations() {
+        let statements = parse("a = e1; b = e2; c = a + b;\nd = c;  e = d;").unwrap();
+        let locations: Vec<_> = statements
+            .iter()
+            .map(|statement| (statement.location.line, statement.location.column))
+            .collect();
+        assert_eq!(locations, [(1, 1), (1, 9), (1, 17), (2, 1), (2, 9)]);
+    }
+
+    #[test]
+    fn error_in_second_statement_on_a_line_has_its_column() {
+        let error = parse("a = e1; b = ;").unwrap_err();
+        assert!(matches!(
+            error.kind,
+            ParseErrorKind::UnexpectedToken(Token {
+                kind: TokenKind::Semicolon,
+                ..
+            })
+        ));
+        assert_eq!((error.location.line, error.location.column), (1, 13));
+    }
+
+    #[test]
+    fn lenient_parse_skips_statement_ending_in_operator() {
+        let source = "b = e1;\nx = a +";
+        assert!(matches!(
+            parse(source),
+            Err(ParseError {
+                kind: ParseErrorKind::UnexpectedEOI,
+                ..
+            })
+        ));
+        let (statements, incomplete) = parse_lenient(source).unwrap();
+        assert_eq!(statements.len(), 1);
+        let incomplete = incomplete.unwrap();
+        assert_eq!((incomplete.line, incomplete.column), (2, 1));
+
+        // an unfinished statement that does not end in an operator is still an error
+        assert!(parse_lenient("b = e1;\nx = a").is_err());
+        assert_eq!(parse_lenient("b = e1;").unwrap().1, None);
+    }
+}
@@ -0,0 +1,37 @@
+struct VertexInput {
+    @builtin(vertex_index) vertex_index: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+struct PolygonVertex {
+    position: vec2<f32>,
+    color: vec4<f32>,
+}
+
+struct PolygonVertices {
+    count: u32,
+    data: array<PolygonVertex>,
+}
+
+@group(0) @binding(0)
+var<storage, read> vertices: PolygonVertices;
+
+@vertex
+fn vertex(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+
+    let vertex = vertices.data[input.vertex_index];
+    output.clip_position = vec4<f32>(vertex.position, 0.0, 1.0);
+    output.color = vertex.color;
+
+    return output;
+}
+
+@fragment
+fn fragment(input: VertexOutput) -> @location(0) vec4<f32> {
+    return input.color;
+}
@@ -2,7 +2,7 @@ use crate::multivector::Multivector;
 use eframe::{egui, wgpu};
 use encase::{ArrayLength, ShaderSize, ShaderType};
 
-#[derive(ShaderType)]
+#[derive(Clone, PartialEq, ShaderType)]
 pub struct GpuCamera {
     pub transform: Multivector,
     pub vertical_height: f32,
@@ -10,13 +10,21 @@ pub struct GpuCamera {
     pub line_thickness: f32,
     pub point_radius: f32,
     pub flavour: u32,
+    pub line_extent: f32,
+    pub outline_width: f32,
+    pub line_orientation: u32,
 }
 
-#[derive(ShaderType)]
+#[derive(Clone, PartialEq, ShaderType)]
 pub struct GpuObject {
     pub value: Multivector,
     pub color: cgmath::Vector3<f32>,
     pub layer: f32,
+    pub point_style: u32,
+    /// Multiplies the line thickness and point radius
+    pub scale: f32,
+    /// Decides what is drawn on top before the layer does
+    pub z_order: i32,
 }
 
 #[derive(ShaderType)]
@@ -26,6 +34,20 @@ struct GpuObjects<'a> {
     data: &'a Vec<GpuObject>,
 }
 
+/// A vertex of a filled polygon, already in clip space
+#[derive(Clone, PartialEq, ShaderType)]
+pub struct GpuPolygonVertex {
+    pub position: cgmath::Vector2<f32>,
+    pub color: cgmath::Vector4<f32>,
+}
+
+#[derive(ShaderType)]
+struct GpuPolygonVertices<'a> {
+    count: ArrayLength,
+    #[size(runtime)]
+    data: &'a Vec<GpuPolygonVertex>,
+}
+
 pub struct RenderState {
     camera_buffer: wgpu::Buffer,
     camera_bind_group: wgpu::BindGroup,
@@ -35,6 +57,37 @@ pub struct RenderState {
     objects_bind_group: wgpu::BindGroup,
 
     objects_render_pipeline: wgpu::RenderPipeline,
+
+    polygons_buffer: wgpu::Buffer,
+    polygons_bind_group_layout: wgpu::BindGroupLayout,
+    polygons_bind_group: wgpu::BindGroup,
+
+    polygons_render_pipeline: wgpu::RenderPipeline,
+
+    /// What was last written to the buffers, so unchanged frames can skip writing them again
+    last_camera: LastWritten<GpuCamera>,
+    last_objects: LastWritten<Vec<GpuObject>>,
+    last_polygons: LastWritten<Vec<GpuPolygonVertex>>,
+}
+
+/// The value that was last written to a buffer
+struct LastWritten<T>(Option<T>);
+
+impl<T> Default for LastWritten<T> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<T: Clone + PartialEq> LastWritten<T> {
+    /// Whether `value` has to be written because it differs from the last value, which it then replaces
+    fn update(&mut self, value: &T) -> bool {
+        if self.0.as_ref() == Some(value) {
+            return false;
+        }
+        self.0 = Some(value.clone());
+        true
+    }
 }
 
 impl RenderState {
@@ -148,6 +201,82 @@ impl RenderState {
                 cache: None,
             });
 
+        let polygons_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Polygons Buffer"),
+            size: GpuPolygonVertices::min_size().get(),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let polygons_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Polygons Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(GpuPolygonVertices::min_size()),
+                    },
+                    count: None,
+                }],
+            });
+        let polygons_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Polygons Bind Group"),
+            layout: &polygons_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: polygons_buffer.as_entire_binding(),
+            }],
+        });
+
+        let polygons_shader = device.create_shader_module(wgpu::include_wgsl!("./polygons.wgsl"));
+
+        let polygons_render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Polygons Render Pipeline Layout"),
+                bind_group_layouts: &[&polygons_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let polygons_render_pipeline =
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Polygons Render Pipeline"),
+                layout: Some(&polygons_render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &polygons_shader,
+                    entry_point: Some("vertex"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Cw,
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &polygons_shader,
+                    entry_point: Some("fragment"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: target_format,
+                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+                cache: None,
+            });
+
         Self {
             camera_buffer,
             camera_bind_group,
@@ -157,6 +286,16 @@ impl RenderState {
             objects_bind_group,
 
             objects_render_pipeline,
+
+            polygons_buffer,
+            polygons_bind_group_layout,
+            polygons_bind_group,
+
+            polygons_render_pipeline,
+
+            last_camera: LastWritten::default(),
+            last_objects: LastWritten::default(),
+            last_polygons: LastWritten::default(),
         }
     }
 }
@@ -164,6 +303,8 @@ impl RenderState {
 pub struct RenderData {
     pub camera: GpuCamera,
     pub objects: Vec<GpuObject>,
+    /// Filled polygons as a triangle list, drawn behind the objects
+    pub polygons: Vec<GpuPolygonVertex>,
 }
 
 impl eframe::egui_wgpu::CallbackTrait for RenderData {
@@ -177,7 +318,7 @@ impl eframe::egui_wgpu::CallbackTrait for RenderData {
     ) -> Vec<wgpu::CommandBuffer> {
         let state: &mut RenderState = callback_resources.get_mut().unwrap();
 
-        {
+        if state.last_camera.update(&self.camera) {
             let mut camera_buffer = queue
                 .write_buffer_with(&state.camera_buffer, 0, GpuCamera::SHADER_SIZE)
                 .unwrap();
@@ -186,7 +327,7 @@ impl eframe::egui_wgpu::CallbackTrait for RenderData {
                 .unwrap();
         }
 
-        {
+        if state.last_objects.update(&self.objects) {
             let objects = GpuObjects {
                 count: ArrayLength,
                 data: &self.objects,
@@ -218,6 +359,38 @@ impl eframe::egui_wgpu::CallbackTrait for RenderData {
                 .unwrap();
         }
 
+        if state.last_polygons.update(&self.polygons) {
+            let polygons = GpuPolygonVertices {
+                count: ArrayLength,
+                data: &self.polygons,
+            };
+
+            let size = polygons.size();
+            if size.get() > state.polygons_buffer.size() {
+                state.polygons_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Polygons Buffer"),
+                    size: size.get(),
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                state.polygons_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Polygons Bind Group"),
+                    layout: &state.polygons_bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: state.polygons_buffer.as_entire_binding(),
+                    }],
+                });
+            }
+
+            let mut polygons_buffer = queue
+                .write_buffer_with(&state.polygons_buffer, 0, size)
+                .unwrap();
+            encase::StorageBuffer::new(&mut *polygons_buffer)
+                .write(&polygons)
+                .unwrap();
+        }
+
         vec![]
     }
 
@@ -229,9 +402,51 @@ impl eframe::egui_wgpu::CallbackTrait for RenderData {
     ) {
         let state: &RenderState = callback_resources.get().unwrap();
 
+        if !self.polygons.is_empty() {
+            render_pass.set_pipeline(&state.polygons_render_pipeline);
+            render_pass.set_bind_group(0, &state.polygons_bind_group, &[]);
+            render_pass.draw(0..self.polygons.len() as u32, 0..1);
+        }
+
         render_pass.set_pipeline(&state.objects_render_pipeline);
         render_pass.set_bind_group(0, &state.camera_bind_group, &[]);
         render_pass.set_bind_group(1, &state.objects_bind_group, &[]);
         render_pass.draw(0..4, 0..1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_frames_are_not_written_again() {
+        let camera = GpuCamera {
+            transform: Multivector::rotor(0.0),
+            vertical_height: 10.0,
+            aspect: 1.5,
+            line_thickness: 0.02,
+            point_radius: 0.05,
+            flavour: 0,
+            line_extent: 0.0,
+            outline_width: 0.01,
+            line_orientation: 0,
+        };
+        let mut last_camera = LastWritten::default();
+        assert!(last_camera.update(&camera));
+        assert!(!last_camera.update(&camera.clone()));
+
+        let moved = GpuCamera {
+            aspect: 2.0,
+            ..camera.clone()
+        };
+        assert!(last_camera.update(&moved));
+        assert!(!last_camera.update(&moved));
+        assert!(last_camera.update(&camera));
+
+        // an empty scene is still written once, so the buffer does not keep old objects
+        let mut last_polygons = LastWritten::<Vec<GpuPolygonVertex>>::default();
+        assert!(last_polygons.update(&vec![]));
+        assert!(!last_polygons.update(&vec![]));
+    }
+}